@@ -0,0 +1,53 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use simple_eval::{Context, Eval, Expr};
+
+const INPUT: &str = "3x^2 + 2x*y - sin(x) / (1 + y^2) + if(x > y, sqrt(x), ln(1 + y))";
+
+// Values of `x` and `y` the expression is evaluated for.
+fn points() -> Vec<(f64, f64)> {
+    (0..1000).map(|i| (i as f64 * 0.01, 5. - i as f64 * 0.003)).collect()
+}
+
+fn tree_walking(c: &mut Criterion) {
+    let mut ctx = Context::new();
+    let expr = Expr::parse_with(INPUT, &ctx).unwrap();
+    let points = points();
+    c.bench_function("eval_with", |b| {
+        b.iter(|| {
+            for &(x, y) in &points {
+                ctx.set_variable("x", x);
+                ctx.set_variable("y", y);
+                black_box(expr.eval_with(&ctx).unwrap());
+            }
+        })
+    });
+    c.bench_function("eval_numeric", |b| {
+        b.iter(|| {
+            for &(x, y) in &points {
+                ctx.set_variable("x", x);
+                ctx.set_variable("y", y);
+                black_box(expr.eval_numeric::<f64>(&ctx).unwrap());
+            }
+        })
+    });
+}
+
+fn compiled(c: &mut Criterion) {
+    let ctx = Context::new();
+    let program = Expr::parse_with(INPUT, &ctx).unwrap().compile(&ctx).unwrap();
+    let (x, y) = (program.slot("x").unwrap(), program.slot("y").unwrap());
+    let points = points();
+    c.bench_function("compiled", |b| {
+        let mut values = [0.; 2];
+        b.iter(|| {
+            for &point in &points {
+                values[x] = point.0;
+                values[y] = point.1;
+                black_box(program.eval(&values).unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, tree_walking, compiled);
+criterion_main!(benches);
@@ -1,23 +1,103 @@
+use std::fmt;
 
+use crate::context::Context;
 use crate::eval::Eval;
-use crate::maph_error::Error;
+use crate::maph_error::{Error, ErrorKinds, Span};
+use crate::value::Value;
 
-#[derive(Debug, PartialEq)]
+/// The golden ratio, `(1 + √5) / 2`.
+const PHI: f64 = 1.618_033_988_749_895;
+/// The Euler–Mascheroni constant.
+const EULER_GAMMA: f64 = 0.577_215_664_901_532_9;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Const {
-    Pi(usize),
-    E(usize),
-    Inf(usize),
+    Pi(Span),
+    E(Span),
+    Inf(Span),
+    Tau(Span),
+    Phi(Span),
+    NaN(Span),
+    EulerGamma(Span),
+    /// The imaginary unit `i` or `j`, only parsed in complex mode and only
+    /// evaluated by [`Expr::eval_complex`](crate::Expr::eval_complex).
+    I(Span),
+    /// A constant registered in the context, resolved when parsing.
+    Custom(String, f64, Span),
+}
+
+impl Const {
+    /// Build the builtin constant called `name` (case insensitive).
+    pub fn new(name: &str, span: Span) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "pi" => Some(Self::Pi(span)),
+            "e" => Some(Self::E(span)),
+            "inf" => Some(Self::Inf(span)),
+            "tau" => Some(Self::Tau(span)),
+            "phi" => Some(Self::Phi(span)),
+            "nan" => Some(Self::NaN(span)),
+            "gamma" => Some(Self::EulerGamma(span)),
+            _ => None,
+        }
+    }
+
+    /// Build the constant called `name`, looking first at the ones registered in `ctx`,
+    /// then at the imaginary unit in complex mode.
+    pub fn with_context(name: &str, span: Span, ctx: &Context) -> Option<Self> {
+        match ctx.get_constant(name) {
+            Some(value) => Some(Self::Custom(name.to_owned(), value, span)),
+            None if ctx.complex() && (name == "i" || name == "j") => Some(Self::I(span)),
+            None => Self::new(name, span),
+        }
+    }
+
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::Pi(span)
+            | Self::E(span)
+            | Self::Inf(span)
+            | Self::Tau(span)
+            | Self::Phi(span)
+            | Self::NaN(span)
+            | Self::EulerGamma(span)
+            | Self::I(span)
+            | Self::Custom(_, _, span) => span,
+        }
+    }
+}
+
+impl fmt::Display for Const {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Pi(_) => "pi",
+            Self::E(_) => "e",
+            Self::Inf(_) => "inf",
+            Self::Tau(_) => "tau",
+            Self::Phi(_) => "phi",
+            Self::NaN(_) => "nan",
+            Self::EulerGamma(_) => "gamma",
+            Self::I(_) => "i",
+            Self::Custom(name, _, _) => name,
+        };
+        f.write_str(name)
+    }
 }
 
 impl Eval for Const {
-    type Output = (f64, usize);
+    type Output = Value;
     type Err = Error;
 
-    fn eval(&self) -> Result<Self::Output, Self::Err> {
+    fn eval_with(&self, _ctx: &Context) -> Result<Self::Output, Self::Err> {
         match self {
-            Self::Pi(span) => Ok((std::f64::consts::PI, *span)),
-            Self::E(span) => Ok((std::f64::consts::E, *span)),
-            Self::Inf(span) => Ok((f64::INFINITY, *span))
+            Self::Pi(_) => Ok(Value::Float(std::f64::consts::PI)),
+            Self::E(_) => Ok(Value::Float(std::f64::consts::E)),
+            Self::Inf(_) => Ok(Value::Float(f64::INFINITY)),
+            Self::Tau(_) => Ok(Value::Float(std::f64::consts::TAU)),
+            Self::Phi(_) => Ok(Value::Float(PHI)),
+            Self::NaN(_) => Ok(Value::Float(f64::NAN)),
+            Self::EulerGamma(_) => Ok(Value::Float(EULER_GAMMA)),
+            Self::I(span) => Err(Error::new(ErrorKinds::Unsupported("i".to_owned()), vec![span.clone()])),
+            Self::Custom(_, value, _) => Ok(Value::Float(*value)),
         }
     }
-}
\ No newline at end of file
+}
@@ -1,44 +1,431 @@
 
+use std::fmt;
 use std::str::FromStr;
 
+use crate::compile::{self, Program};
+use crate::complex::{self, Complex};
+use crate::context::Context;
+use crate::derive;
+use crate::interval::{self, Interval};
 use crate::eval::Eval;
-use crate::maph_error::Error;
+use crate::export;
+use crate::maph_error::{Error, ErrorKinds, Span};
+use crate::numeric::{self, Numeric};
 use crate::parse::Parse;
+use crate::simplify;
+use crate::value::Value;
 
 use super::tree::TreeNode;
 use super::function::Function;
 use super::token::Tokenize;
+use super::token::operator::{Operator, UnaryOp};
 use super::consts::Const;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     Node(Box<TreeNode>),
-    Number(f64, usize),
+    /// A float, with the digits of its literal when the float does not hold
+    /// them all, which exact backends of [`Expr::eval_numeric`] evaluate.
+    Number(f64, Option<String>, Span),
+    Int(i64, Span),
     Const(Const),
     Function(Box<Function>),
+    Variable(String, Span),
+    Unary(UnaryOp, Box<Expr>, Span),
+    /// `[lo, hi]`, only evaluated by [`Expr::eval_interval`].
+    Interval(Box<Expr>, Box<Expr>, Span),
+}
+
+impl Expr {
+    /// Parse `s` with the number format of `ctx`, resolving function names
+    /// against the ones registered in it.
+    pub fn parse_with(s: &str, ctx: &Context) -> Result<Self, Vec<Error>> {
+        let calulation = s.tokenize_with(ctx.locale())?;
+        let parsable = calulation.pre_parse(ctx)?;
+        parsable.parse(ctx)
+    }
+
+    /// Evaluate the expression to the number type `N`, such as `f64` or the
+    /// exact `Ratio` of the `rational` feature. Booleans are only allowed as
+    /// the condition of an `if`, and bitwise operators are not supported.
+    /// Custom functions compute with floats, so an exact backend treats their
+    /// results as irrational, see [`Irrational`](crate::Irrational).
+    pub fn eval_numeric<N: Numeric>(&self, ctx: &Context) -> Result<N, Error> {
+        numeric::eval(self, ctx)
+    }
+
+    /// Evaluate the expression to a complex number, so `sqrt(-4)` is `2i`.
+    /// Booleans are only allowed as the condition of an `if`, and the other
+    /// operators than `+`, `-`, `*`, `/` and `^` need real operands.
+    pub fn eval_complex(&self, ctx: &Context) -> Result<Complex, Error> {
+        complex::eval(self, ctx)
+    }
+
+    /// Evaluate the expression to an interval holding every possible result,
+    /// numbers being written as `5±0.1` or `[4.9, 5.1]`. Booleans are only
+    /// allowed as the condition of an `if`, which must hold for the whole
+    /// interval or for none of it, and bitwise operators are not supported.
+    pub fn eval_interval(&self, ctx: &Context) -> Result<Interval, Error> {
+        interval::eval(self, ctx)
+    }
+
+    /// Derivative of the expression with respect to the variable `var`, see
+    /// [`derive`](crate::derive).
+    pub fn derive(&self, var: &str) -> Result<Expr, Error> {
+        derive::derive(self, var)
+    }
+
+    /// Simplify the expression, see [`Expr::simplify_with`].
+    pub fn simplify(self) -> Self {
+        self.simplify_with(&Context::default())
+    }
+
+    /// Simplify the expression: operations on integers are replaced by their
+    /// result when it is an integer, which every backend computes exactly,
+    /// identities such as `x + 0`, `x * 1` or `x ^ 1` are applied, as are
+    /// `x * 0` and `x - x` when `x` is a finite number or constant, which
+    /// can not be an error, and the operands of `+` and `*` chains are put in
+    /// a canonical order, as
+    /// `2 * x * y` and `x + y + 1`. Named constants and variables are kept,
+    /// `ctx` only providing the settings of the evaluation such as the
+    /// rounding mode. Every node keeps the span of the operands it covers.
+    pub fn simplify_with(self, ctx: &Context) -> Self {
+        simplify::simplify(self, ctx)
+    }
+
+    /// LaTeX of the expression: `/` is a `\\frac`, `^` a superscript, and
+    /// functions and constants use their usual notation, as `\\sqrt[3]{x}`,
+    /// `\\log_{2}` or `\\pi`.
+    pub fn to_latex(&self) -> String {
+        export::latex(self)
+    }
+
+    /// Presentation MathML of the expression, in a `math` element, with the
+    /// same notation as [`Expr::to_latex`].
+    pub fn to_mathml(&self) -> String {
+        format!("<math xmlns=\"http://www.w3.org/1998/Math/MathML\">{}</math>", export::mathml(self))
+    }
+
+    /// Compile the expression to a [`Program`], which evaluates it to a
+    /// float like [`Expr::eval_numeric`] but much faster when it is evaluated
+    /// for many values of its variables. Constants, custom functions and the
+    /// rounding mode are taken from `ctx` once, and operations which are not
+    /// supported, such as bitwise operators, are errors at compile time.
+    pub fn compile(&self, ctx: &Context) -> Result<Program, Error> {
+        compile::compile(self, ctx)
+    }
+
+    /// Range of the input covered by the expression.
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::Node(node) => &node.span,
+            Self::Number(_, _, span)
+            | Self::Int(_, span)
+            | Self::Variable(_, span)
+            | Self::Unary(_, _, span)
+            | Self::Interval(_, _, span) => span,
+            Self::Const(c) => c.span(),
+            Self::Function(fnc) => fnc.span(),
+        }
+    }
+}
+
+impl Expr {
+    // How tightly the expression binds its operands, from the precedence of
+    // its operator. A prefix operator only binds tighter than `^`.
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Node(node) => node.op.precedence(),
+            Self::Unary(..) => Operator::Pow.precedence(),
+            Self::Int(n, _) if *n < 0 => Operator::Pow.precedence(),
+            Self::Number(n, ..) if n.is_sign_negative() => Operator::Pow.precedence(),
+            _ => u8::MAX,
+        }
+    }
+
+    // Write the expression as an operand of an operator, in brackets if it
+    // binds less tightly than `min_precedence`.
+    pub(crate) fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, min_precedence: u8) -> fmt::Result {
+        if self.precedence() < min_precedence {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+/// Infix form parsed back to the same expression, with the canonical names of
+/// the functions and `*` for implicit multiplications, as `2 * sin(x) ^ 2`.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Node(node) => write!(f, "{}", node),
+            // The shortest form telling a float, `2.0` and not `2`.
+            Self::Number(_, Some(digits), _) => write!(f, "{}", digits),
+            Self::Number(n, None, _) => write!(f, "{:?}", n),
+            Self::Int(n, _) => write!(f, "{}", n),
+            Self::Const(c) => write!(f, "{}", c),
+            Self::Function(fnc) => write!(f, "{}", fnc),
+            Self::Variable(name, _) => f.write_str(name),
+            Self::Unary(op, expr, _) => {
+                write!(f, "{}", op)?;
+                expr.fmt_operand(f, Operator::Pow.precedence())
+            }
+            Self::Interval(lo, hi, _) => write!(f, "[{}, {}]", lo, hi),
+        }
+    }
 }
 
 impl FromStr for Expr {
     type Err = Vec<Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let calulation = s.tokenize()?;
-        let parsable = calulation.pre_parse()?;
-        Ok(parsable.parse()?)
+        Self::parse_with(s, &Context::default())
     }
 }
 
 impl Eval for Expr {
-    type Output = (f64, usize);
+    type Output = Value;
     type Err = Error;
 
-    fn eval(&self) -> Result<Self::Output, Self::Err> {
+    fn eval_with(&self, ctx: &Context) -> Result<Self::Output, Self::Err> {
         match self {
-            Self::Node(node) => node.eval(),
-            Self::Number(n, span) => Ok((*n, *span)),
-            Self::Const(c) => c.eval(),
-            Self::Function(fnc) => fnc.eval()
+            Self::Node(node) => node.eval_with(ctx),
+            Self::Number(n, ..) => Ok(Value::Float(*n)),
+            Self::Int(n, _) => Ok(Value::Int(*n)),
+            Self::Const(c) => c.eval_with(ctx),
+            Self::Function(fnc) => fnc.eval_with(ctx),
+            Self::Variable(name, span) => match ctx.get_variable(name) {
+                Some(value) => Ok(value),
+                None => Err(Error::new(ErrorKinds::UnknownVariable(name.clone()), vec![span.clone()])),
+            },
+            Self::Unary(op, expr, _) => {
+                let value = expr.eval_with(ctx)?;
+                match (op, value) {
+                    // `!` is a logical not on a boolean and a bitwise not on an integer.
+                    (UnaryOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+                    (UnaryOp::Not, value) => Ok(Value::Int(!value.to_integer(expr.span())?)),
+                    (UnaryOp::Neg, Value::Int(n)) => Ok(n.checked_neg().map_or(Value::Float(-(n as f64)), Value::Int)),
+                    (UnaryOp::Neg, value) => Ok(Value::Float(-value.to_number(expr.span())?)),
+                    (UnaryOp::Plus, value) => value.to_number(expr.span()).map(|_| value),
+                }
+            }
+            Self::Interval(_, _, span) => Err(Error::new(ErrorKinds::Unsupported("[lo, hi]".to_owned()), vec![span.clone()])),
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numeric::Rounding;
+    use crate::value::ValueType;
+
+    #[test]
+    fn eval_from_str() {
+        let expr = "2-3 * (4 - 1) ^ 2 // 4".parse::<Expr>().unwrap();
+        assert_eq!(expr.eval(), Ok(Value::Int(-4)));
+        assert_eq!(expr.span(), &(0..22));
+    }
+
+    #[test]
+    fn eval_function() {
+        let expr = "sqrt(16) + abs(-2)".parse::<Expr>().unwrap();
+        assert_eq!(expr.eval(), Ok(Value::Float(6.)));
+    }
+
+    #[test]
+    fn eval_round_digits() {
+        let mut ctx = Context::new();
+        let round = |input: &str, ctx: &Context| Expr::parse_with(input, ctx).unwrap().eval_with(ctx);
+        assert_eq!(round("round(0.1 + 0.2, 2)", &ctx), Ok(Value::Float(0.3)));
+        assert_eq!(round("round(1250, -2) + round(-2.5)", &ctx), Ok(Value::Float(1297.)));
+        assert_eq!(
+            round("round(2, 0.5)", &ctx),
+            Err(Error::new(ErrorKinds::NotAnInteger(0.5), vec![9..12]))
+        );
+        ctx.set_rounding(Rounding::HalfEven);
+        assert_eq!(round("round(1250, -2) + round(-2.5)", &ctx), Ok(Value::Float(1198.)));
+    }
+
+    #[test]
+    fn eval_unary() {
+        let expr = "-2^2 + -(x+1) * -sin(x) + 2-3".parse::<Expr>().unwrap();
+        let mut ctx = Context::new();
+        ctx.set_variable("x", 0.);
+        assert_eq!(expr.eval_with(&ctx), Ok(Value::Float(-5.)));
+    }
+
+    #[test]
+    fn eval_bitwise() {
+        let expr = "0xF0 | 0b11 & 6 xor 1 << 2 + !0".parse::<Expr>().unwrap();
+        // `0xF0 | ((0b11 & 6) xor (1 << (2 + -1)))`
+        assert_eq!(expr.eval(), Ok(Value::Int(240)));
+    }
+
+    #[test]
+    fn eval_not_an_integer() {
+        let expr = "6 & 2.5 * 2".parse::<Expr>().unwrap();
+        assert_eq!(expr.eval(), Ok(Value::Int(4)));
+        let expr = "1 << 0.5 + !1.5".parse::<Expr>().unwrap();
+        assert_eq!(expr.eval(), Err(Error::new(ErrorKinds::NotAnInteger(1.5), vec![12..15])));
+        let expr = "1 << 64".parse::<Expr>().unwrap();
+        assert_eq!(expr.eval(), Err(Error::new(ErrorKinds::NotInRange(0., 63.), vec![5..7])));
+    }
+
+    #[test]
+    fn eval_comparison_and_logical() {
+        let expr = "if(age >= 18 && score > 0.5, 1, 0)".parse::<Expr>().unwrap();
+        let mut ctx = Context::new();
+        ctx.set_variable("age", 20);
+        ctx.set_variable("score", 0.7);
+        assert_eq!(expr.eval_with(&ctx), Ok(Value::Int(1)));
+        ctx.set_variable("age", 17);
+        assert_eq!(expr.eval_with(&ctx), Ok(Value::Int(0)));
+
+        let expr = "!(1 < 2) || 2 & 3 == 2 && !!(0 != 0) == (1 > 2)".parse::<Expr>().unwrap();
+        assert_eq!(expr.eval(), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn eval_lazily() {
+        let mut ctx = Context::new();
+        ctx.set_variable("x", 0.);
+        for (input, result) in [
+            ("if(x > 0, 1 / x, 0)", Value::Int(0)),
+            ("x != 0 && 1 / x > 1", Value::Bool(false)),
+            ("x == 0 || 1 / x > 1", Value::Bool(true)),
+        ] {
+            let expr = input.parse::<Expr>().unwrap();
+            assert_eq!(expr.eval_with(&ctx), Ok(result));
+        }
+    }
+
+    #[test]
+    fn eval_types() {
+        for (input, result) in [
+            ("7 / 2", Value::Float(3.5)),
+            ("7 // 2 + 7 % 2", Value::Int(4)),
+            ("2 ^ 62 * 4", Value::Float(2f64.powi(64))),
+            ("2 ^ -1", Value::Float(0.5)),
+            ("1 + 0.5", Value::Float(1.5)),
+            ("!3", Value::Int(-4)),
+            ("(1 < 2) xor !(2 < 3)", Value::Bool(true)),
+        ] {
+            assert_eq!(input.parse::<Expr>().unwrap().eval(), Ok(result));
+        }
+
+        let mut ctx = Context::new();
+        ctx.set_variable("flag", true);
+        let expr = "if(flag, 1, 2) + 1".parse::<Expr>().unwrap();
+        assert_eq!(expr.eval_with(&ctx), Ok(Value::Int(2)));
+        let expr = "flag == (1 < 2)".parse::<Expr>().unwrap();
+        assert_eq!(expr.eval_with(&ctx), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn eval_type_errors() {
+        let expr = "!2 + !(2 > 1)".parse::<Expr>().unwrap();
+        assert_eq!(
+            expr.eval(),
+            Err(Error::new(ErrorKinds::TypeMismatch(ValueType::Int, ValueType::Bool), vec![0..2, 5..13]))
+        );
+        let expr = "(1 < 2) < 3.5".parse::<Expr>().unwrap();
+        assert_eq!(
+            expr.eval(),
+            Err(Error::new(ErrorKinds::TypeMismatch(ValueType::Bool, ValueType::Float), vec![1..6, 10..13]))
+        );
+        let expr = "sqrt(1 == 1)".parse::<Expr>().unwrap();
+        assert_eq!(expr.eval(), Err(Error::new(ErrorKinds::UnexpectedBoolean, vec![5..11])));
+        let expr = "7 % (2 - 2)".parse::<Expr>().unwrap();
+        assert_eq!(expr.eval(), Err(Error::new(ErrorKinds::ModuloByZero, vec![5..10])));
+        let expr = "5.5 % 0".parse::<Expr>().unwrap();
+        assert_eq!(expr.eval(), Err(Error::new(ErrorKinds::ModuloByZero, vec![6..7])));
+    }
+
+    #[test]
+    fn eval_variables() {
+        let expr = "x * 2 + y".parse::<Expr>().unwrap();
+        let mut ctx = Context::new();
+        ctx.set_variable("x", 3.);
+        ctx.set_variable("y", 0.5);
+        assert_eq!(expr.eval_with(&ctx), Ok(Value::Float(6.5)));
+        ctx.set_variable("x", -1.);
+        assert_eq!(expr.eval_with(&ctx), Ok(Value::Float(-1.5)));
+    }
+
+    #[test]
+    fn eval_unknown_variable() {
+        let expr = "x * 2 + y".parse::<Expr>().unwrap();
+        let mut ctx = Context::new();
+        ctx.set_variable("x", 3.);
+        assert_eq!(
+            expr.eval_with(&ctx),
+            Err(Error::new(ErrorKinds::UnknownVariable("y".to_owned()), vec![8..9]))
+        );
+    }
+
+    #[test]
+    fn eval_custom_function() {
+        let mut ctx = Context::new();
+        ctx.set_function("double", 1, |args| args[0] * 2.);
+        ctx.set_variable("x", 4.);
+        let expr = Expr::parse_with("double(x + 1) - 1", &ctx).unwrap();
+        assert_eq!(expr.eval_with(&ctx), Ok(Value::Float(9.)));
+    }
+
+    #[test]
+    fn eval_multiple_arguments() {
+        let expr = "max(1, logb(8, 2), atan2(1, 1) * 4) + min(-2, 0)".parse::<Expr>().unwrap();
+        assert_eq!(expr.eval(), Ok(Value::Float(std::f64::consts::PI - 2.)));
+
+        let mut ctx = Context::new();
+        ctx.set_function("lerp", 3, |args| args[0] + (args[1] - args[0]) * args[2]);
+        let expr = Expr::parse_with("lerp(2, 4, 0.25)", &ctx).unwrap();
+        assert_eq!(expr.eval_with(&ctx), Ok(Value::Float(2.5)));
+    }
+
+    #[test]
+    fn display_expr() {
+        for (input, output) in [
+            ("((1 + 2)) * 3 - (4 - x)", "(1 + 2) * 3 - (4 - x)"),
+            ("2 ^ (3 ^ x) + (2 ^ 3) ^ x", "2 ^ 3 ^ x + (2 ^ 3) ^ x"),
+            ("-x^2 + (-x)^2 - -(x + 1)", "-x ^ 2 + (-x) ^ 2 - -(x + 1)"),
+            ("2x√(y) / 2.0 // 1e-7", "2 * x * sqrt(y) / 2.0 // 1e-7"),
+            ("ATAN2(1, x) + Round(x, 2) + max(x)", "atan2(1, x) + round(x, 2) + max(x)"),
+            ("!(x < 1 && y) || 0xF0 | 1 xor 2", "!(x < 1 && y) || 240 | 1 xor 2"),
+            ("[1, 2] * (PI ± 0.5) - (x ± 1)", "[1, 2] * pi ± 0.5 - x ± 1"),
+        ] {
+            assert_eq!(input.parse::<Expr>().unwrap().to_string(), output);
+        }
+        assert_eq!(Expr::Int(-2, 0..2).to_string(), "-2");
+        let pow = Expr::Node(Box::new(TreeNode::new(Expr::Int(-2, 0..2), Operator::Pow, Expr::Int(2, 3..4), 0..4)));
+        assert_eq!(pow.to_string(), "(-2) ^ 2");
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let mut ctx = Context::new();
+        ctx.set_function("lerp", 3, |args| args[0] + (args[1] - args[0]) * args[2]);
+        ctx.set_constant("g", 9.81);
+        for input in [
+            "a - (b - c) - d / (e * f) / g ^ h ^ -i",
+            "if(x >= 1 || !(y != 2), lerp(x, y, 0.25), -sin(x)^2)",
+            "3x^2 // (1 << 2 >> 1) % 7 & (x | 3) == 2 xor tau",
+            "((x)) ± 1e300 * -(gamma + phi) + cbrq(ln(log(logb(8, 2))))",
+        ] {
+            let expr = Expr::parse_with(input, &ctx).unwrap();
+            let printed = Expr::parse_with(&expr.to_string(), &ctx).unwrap();
+            assert!(crate::simplify::same(&expr, &printed), "{} is not {}", printed, input);
+        }
+    }
+
+    #[test]
+    fn eval_error_span_covers_subexpression() {
+        let expr = "2 + 1 / (x - x)".parse::<Expr>().unwrap();
+        let mut ctx = Context::new();
+        ctx.set_variable("x", 1.);
+        assert_eq!(expr.eval_with(&ctx), Err(Error::new(ErrorKinds::DivisionByZero, vec![9..14])));
+        assert_eq!(expr.span(), &(0..15));
+    }
+}
@@ -1,86 +1,385 @@
+use std::fmt;
+use std::sync::Arc;
 
+use crate::context::Context;
 use crate::eval::Eval;
 use crate::maph_error::{
     Error,
-    ErrorKinds
+    ErrorKinds,
+    Span
 };
+use crate::numeric::Numeric;
+use crate::value::Value;
 use super::expr::Expr;
 
-pub const FUNCTION_NAME: &[&str] = &["sqrt", "cbrq", "abs", "sin", "cos", "tan", "asin", "acos", "atan", "ln", "log", "logb", "round", "floor", "ceil", "trunc", "frac", "deg", "rad"];
+pub const FUNCTION_NAME: &[&str] = &["sqrt", "cbrq", "abs", "sin", "cos", "tan", "asin", "acos", "atan", "atan2", "ln", "log", "logb", "round", "floor", "ceil", "trunc", "frac", "deg", "rad", "max", "min", "if", "re", "im", "arg", "conj"];
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Function {
-    Sqrt(Expr, usize),
-    Cbrq(Expr, usize),
-    Abs(Expr, usize),
-    Sin(Expr, usize),
-    Cos(Expr, usize),
-    Tan(Expr, usize),
-    ArcSin(Expr, usize),
-    ArcCos(Expr, usize),
-    ArcTan(Expr, usize),
-    Ln(Expr, usize),
-    Log(Expr, usize),
-    LogBased(Expr, f64, usize),
-    Round(Expr, usize),
-    Floor(Expr, usize),
-    Ceil(Expr, usize),
-    Trunc(Expr, usize),
-    Frac(Expr, usize),
-    Deg(Expr, usize),
-    Rad(Expr, usize),
+    Sqrt(Expr, Span),
+    Cbrq(Expr, Span),
+    Abs(Expr, Span),
+    Sin(Expr, Span),
+    Cos(Expr, Span),
+    Tan(Expr, Span),
+    ArcSin(Expr, Span),
+    ArcCos(Expr, Span),
+    ArcTan(Expr, Span),
+    ArcTan2(Expr, Expr, Span),
+    Ln(Expr, Span),
+    Log(Expr, Span),
+    LogBased(Expr, Expr, Span),
+    /// `round(x)` or `round(x, digits)`.
+    Round(Expr, Option<Expr>, Span),
+    Floor(Expr, Span),
+    Ceil(Expr, Span),
+    Trunc(Expr, Span),
+    Frac(Expr, Span),
+    Deg(Expr, Span),
+    Rad(Expr, Span),
+    Max(Vec<Expr>, Span),
+    Min(Vec<Expr>, Span),
+    /// `if(condition, then, else)`, only the chosen branch is evaluated.
+    If(Expr, Expr, Expr, Span),
+    /// Real part.
+    Re(Expr, Span),
+    /// Imaginary part, `0` for a real number.
+    Im(Expr, Span),
+    /// Argument, `0` or `π` for a real number.
+    Arg(Expr, Span),
+    /// Complex conjugate.
+    Conj(Expr, Span),
+    Custom(CustomFunction, Vec<Expr>, Span),
+}
+
+type FunctionPtr = Arc<dyn Fn(&[f64]) -> f64 + Send + Sync>;
+
+/// A function registered at runtime in a [`Context`].
+#[derive(Clone)]
+pub struct CustomFunction {
+    pub name: String,
+    pub arity: usize,
+    function: FunctionPtr,
+}
+
+impl CustomFunction {
+    pub fn new(name: impl Into<String>, arity: usize, function: impl Fn(&[f64]) -> f64 + Send + Sync + 'static) -> Self {
+        Self {
+            name: name.into(),
+            arity,
+            function: Arc::new(function),
+        }
+    }
+
+    #[inline]
+    pub fn call(&self, args: &[f64]) -> f64 {
+        (self.function)(args)
+    }
+}
+
+impl fmt::Debug for CustomFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomFunction")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish()
+    }
+}
+
+impl PartialEq for CustomFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.arity == other.arity && Arc::ptr_eq(&self.function, &other.function)
+    }
+}
+
+impl Function {
+    /// Build the function called `name` (case insensitive) applied to `args`.
+    pub fn new(name: &str, args: Vec<Expr>, span: Span) -> Result<Self, ErrorKinds> {
+        let lowercase = name.to_lowercase();
+        let expected = match lowercase.as_str() {
+            "max" | "min" if args.is_empty() => return Err(ErrorKinds::WrongArgumentCount { expected: 1, found: 0 }),
+            "max" => return Ok(Self::Max(args, span)),
+            "min" => return Ok(Self::Min(args, span)),
+            "atan2" | "logb" => 2,
+            "round" if args.len() >= 2 => 2,
+            "if" => 3,
+            known if FUNCTION_NAME.contains(&known) => 1,
+            _ => return Err(ErrorKinds::UnknowFonction(name.to_owned())),
+        };
+        if args.len() != expected {
+            return Err(ErrorKinds::WrongArgumentCount { expected, found: args.len() });
+        }
+
+        let mut args = args.into_iter();
+        let mut arg = || args.next().unwrap();
+        let function = match lowercase.as_str() {
+            "sqrt" => Self::Sqrt(arg(), span),
+            "cbrq" => Self::Cbrq(arg(), span),
+            "abs" => Self::Abs(arg(), span),
+            "sin" => Self::Sin(arg(), span),
+            "cos" => Self::Cos(arg(), span),
+            "tan" => Self::Tan(arg(), span),
+            "asin" => Self::ArcSin(arg(), span),
+            "acos" => Self::ArcCos(arg(), span),
+            "atan" => Self::ArcTan(arg(), span),
+            "atan2" => Self::ArcTan2(arg(), arg(), span),
+            "ln" => Self::Ln(arg(), span),
+            "log" => Self::Log(arg(), span),
+            "logb" => Self::LogBased(arg(), arg(), span),
+            "round" => {
+                let expr = arg();
+                Self::Round(expr, if expected == 2 { Some(arg()) } else { None }, span)
+            }
+            "floor" => Self::Floor(arg(), span),
+            "ceil" => Self::Ceil(arg(), span),
+            "trunc" => Self::Trunc(arg(), span),
+            "frac" => Self::Frac(arg(), span),
+            "deg" => Self::Deg(arg(), span),
+            "rad" => Self::Rad(arg(), span),
+            "if" => Self::If(arg(), arg(), arg(), span),
+            "re" => Self::Re(arg(), span),
+            "im" => Self::Im(arg(), span),
+            "arg" => Self::Arg(arg(), span),
+            "conj" => Self::Conj(arg(), span),
+            _ => unreachable!(),
+        };
+        Ok(function)
+    }
+
+    /// Span of the whole call, from the name to the closing bracket.
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::Sqrt(_, span)
+            | Self::Cbrq(_, span)
+            | Self::Abs(_, span)
+            | Self::Sin(_, span)
+            | Self::Cos(_, span)
+            | Self::Tan(_, span)
+            | Self::ArcSin(_, span)
+            | Self::ArcCos(_, span)
+            | Self::ArcTan(_, span)
+            | Self::ArcTan2(_, _, span)
+            | Self::Ln(_, span)
+            | Self::Log(_, span)
+            | Self::LogBased(_, _, span)
+            | Self::Round(_, _, span)
+            | Self::Floor(_, span)
+            | Self::Ceil(_, span)
+            | Self::Trunc(_, span)
+            | Self::Frac(_, span)
+            | Self::Deg(_, span)
+            | Self::Rad(_, span)
+            | Self::Max(_, span)
+            | Self::Min(_, span)
+            | Self::If(_, _, _, span)
+            | Self::Re(_, span)
+            | Self::Im(_, span)
+            | Self::Arg(_, span)
+            | Self::Conj(_, span)
+            | Self::Custom(_, _, span) => span,
+        }
+    }
+
+    /// Name the function is called by, in lowercase for a builtin one.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Sqrt(..) => "sqrt",
+            Self::Cbrq(..) => "cbrq",
+            Self::Abs(..) => "abs",
+            Self::Sin(..) => "sin",
+            Self::Cos(..) => "cos",
+            Self::Tan(..) => "tan",
+            Self::ArcSin(..) => "asin",
+            Self::ArcCos(..) => "acos",
+            Self::ArcTan(..) => "atan",
+            Self::ArcTan2(..) => "atan2",
+            Self::Ln(..) => "ln",
+            Self::Log(..) => "log",
+            Self::LogBased(..) => "logb",
+            Self::Round(..) => "round",
+            Self::Floor(..) => "floor",
+            Self::Ceil(..) => "ceil",
+            Self::Trunc(..) => "trunc",
+            Self::Frac(..) => "frac",
+            Self::Deg(..) => "deg",
+            Self::Rad(..) => "rad",
+            Self::Max(..) => "max",
+            Self::Min(..) => "min",
+            Self::If(..) => "if",
+            Self::Re(..) => "re",
+            Self::Im(..) => "im",
+            Self::Arg(..) => "arg",
+            Self::Conj(..) => "conj",
+            Self::Custom(function, ..) => &function.name,
+        }
+    }
+
+    /// Arguments of the function, in the order they are written.
+    pub fn args(&self) -> Vec<&Expr> {
+        match self {
+            Self::Sqrt(expr, _)
+            | Self::Cbrq(expr, _)
+            | Self::Abs(expr, _)
+            | Self::Sin(expr, _)
+            | Self::Cos(expr, _)
+            | Self::Tan(expr, _)
+            | Self::ArcSin(expr, _)
+            | Self::ArcCos(expr, _)
+            | Self::ArcTan(expr, _)
+            | Self::Ln(expr, _)
+            | Self::Log(expr, _)
+            | Self::Floor(expr, _)
+            | Self::Ceil(expr, _)
+            | Self::Trunc(expr, _)
+            | Self::Frac(expr, _)
+            | Self::Deg(expr, _)
+            | Self::Rad(expr, _)
+            | Self::Re(expr, _)
+            | Self::Im(expr, _)
+            | Self::Arg(expr, _)
+            | Self::Conj(expr, _) => vec![expr],
+            Self::ArcTan2(l, r, _) | Self::LogBased(l, r, _) => vec![l, r],
+            Self::Round(expr, digits, _) => std::iter::once(expr).chain(digits).collect(),
+            Self::If(condition, then, otherwise, _) => vec![condition, then, otherwise],
+            Self::Max(args, _) | Self::Min(args, _) | Self::Custom(_, args, _) => args.iter().collect(),
+        }
+    }
+
+    /// The same function applied to the arguments transformed by `f`.
+    pub(crate) fn map_args(self, mut f: impl FnMut(Expr) -> Expr) -> Self {
+        match self {
+            Self::Sqrt(expr, span) => Self::Sqrt(f(expr), span),
+            Self::Cbrq(expr, span) => Self::Cbrq(f(expr), span),
+            Self::Abs(expr, span) => Self::Abs(f(expr), span),
+            Self::Sin(expr, span) => Self::Sin(f(expr), span),
+            Self::Cos(expr, span) => Self::Cos(f(expr), span),
+            Self::Tan(expr, span) => Self::Tan(f(expr), span),
+            Self::ArcSin(expr, span) => Self::ArcSin(f(expr), span),
+            Self::ArcCos(expr, span) => Self::ArcCos(f(expr), span),
+            Self::ArcTan(expr, span) => Self::ArcTan(f(expr), span),
+            Self::ArcTan2(y, x, span) => Self::ArcTan2(f(y), f(x), span),
+            Self::Ln(expr, span) => Self::Ln(f(expr), span),
+            Self::Log(expr, span) => Self::Log(f(expr), span),
+            Self::LogBased(expr, base, span) => Self::LogBased(f(expr), f(base), span),
+            Self::Round(expr, digits, span) => Self::Round(f(expr), digits.map(f), span),
+            Self::Floor(expr, span) => Self::Floor(f(expr), span),
+            Self::Ceil(expr, span) => Self::Ceil(f(expr), span),
+            Self::Trunc(expr, span) => Self::Trunc(f(expr), span),
+            Self::Frac(expr, span) => Self::Frac(f(expr), span),
+            Self::Deg(expr, span) => Self::Deg(f(expr), span),
+            Self::Rad(expr, span) => Self::Rad(f(expr), span),
+            Self::Max(args, span) => Self::Max(args.into_iter().map(f).collect(), span),
+            Self::Min(args, span) => Self::Min(args.into_iter().map(f).collect(), span),
+            Self::If(condition, then, otherwise, span) => Self::If(f(condition), f(then), f(otherwise), span),
+            Self::Re(expr, span) => Self::Re(f(expr), span),
+            Self::Im(expr, span) => Self::Im(f(expr), span),
+            Self::Arg(expr, span) => Self::Arg(f(expr), span),
+            Self::Conj(expr, span) => Self::Conj(f(expr), span),
+            Self::Custom(function, args, span) => Self::Custom(function, args.into_iter().map(f).collect(), span),
+        }
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let args = self.args().iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        write!(f, "{}({})", self.name(), args.join(", "))
+    }
 }
 
 impl Eval for Function {
-    type Output = (f64, usize);
+    type Output = Value;
     type Err = Error;
 
-    fn eval(&self) -> Result<Self::Output, Self::Err> {
+    fn eval_with(&self, ctx: &Context) -> Result<Self::Output, Self::Err> {
         match self {
             Function::Sqrt(expr, span) => {
-                let number = expr.eval()?.0;
+                let number = number(expr, ctx)?;
                 if number < 0. {
-                    return Err(Error::new(ErrorKinds::NegativeSqrt, vec![*span]));
+                    return Err(Error::new(ErrorKinds::NegativeSqrt, vec![span.clone()]));
                 }
-                Ok((number.sqrt(), *span))
+                Ok(Value::Float(number.sqrt()))
             }
-            Function::Cbrq(expr, span) => Ok((expr.eval()?.0.cbrt(), *span)),
-            Function::Abs(expr, span) => Ok((expr.eval()?.0.abs(), *span)),
-            Function::Sin(expr, span) => Ok((expr.eval()?.0.sin(), *span)),
-            Function::Cos(expr, span) => Ok((expr.eval()?.0.cos(), *span)),
-            Function::Tan(expr, span) => Ok((expr.eval()?.0.tan(), *span)),
+            Function::Cbrq(expr, _) => Ok(Value::Float(number(expr, ctx)?.cbrt())),
+            Function::Abs(expr, _) => Ok(Value::Float(number(expr, ctx)?.abs())),
+            Function::Sin(expr, _) => Ok(Value::Float(number(expr, ctx)?.sin())),
+            Function::Cos(expr, _) => Ok(Value::Float(number(expr, ctx)?.cos())),
+            Function::Tan(expr, _) => Ok(Value::Float(number(expr, ctx)?.tan())),
             Function::ArcSin(expr, span) => {
-                let number = expr.eval()?.0;
-                if number < -1. || number > 1. {
-                    return Err(Error::new(ErrorKinds::NotInRange(-1., 1.), vec![*span]));
+                let number = number(expr, ctx)?;
+                if !(-1. ..=1.).contains(&number) {
+                    return Err(Error::new(ErrorKinds::NotInRange(-1., 1.), vec![span.clone()]));
                 }
-                Ok((number.asin(), *span))
+                Ok(Value::Float(number.asin()))
             },
             Function::ArcCos(expr, span) => {
-                let number = expr.eval()?.0;
-                if number < -1. || number > 1. {
-                    return Err(Error::new(ErrorKinds::NotInRange(-1., 1.), vec![*span]));
+                let number = number(expr, ctx)?;
+                if !(-1. ..=1.).contains(&number) {
+                    return Err(Error::new(ErrorKinds::NotInRange(-1., 1.), vec![span.clone()]));
                 }
-                Ok((number.acos(), *span))
+                Ok(Value::Float(number.acos()))
             },
-            Function::ArcTan(expr, span) => Ok((expr.eval()?.0.atan(), *span)),
-            Function::Ln(expr, span) => Ok((expr.eval()?.0.ln(), *span)),
-            Function::Log(expr, span) => Ok((expr.eval()?.0.log10(), *span)),
-            Function::LogBased(expr, base, span) => {
-                let number = expr.eval()?.0;
-                match base {
-                    two if two - 2. <= f64::EPSILON => Ok((number.log2(), *span)),
-                    ten if ten - 10. <= f64::EPSILON => Ok((number.log10(), *span)),
-                    b => Ok((number.log(*b), *span)),
+            Function::ArcTan(expr, _) => Ok(Value::Float(number(expr, ctx)?.atan())),
+            Function::ArcTan2(y, x, _) => Ok(Value::Float(number(y, ctx)?.atan2(number(x, ctx)?))),
+            Function::Ln(expr, _) => Ok(Value::Float(number(expr, ctx)?.ln())),
+            Function::Log(expr, _) => Ok(Value::Float(number(expr, ctx)?.log10())),
+            Function::LogBased(expr, base, _) => Ok(Value::Float(log_based(number(expr, ctx)?, number(base, ctx)?))),
+            Function::Round(expr, digits, _) => {
+                let number = number(expr, ctx)?;
+                let digits = match digits {
+                    Some(digits) => digits.eval_with(ctx)?.to_integer(digits.span())?,
+                    None => 0,
+                };
+                Ok(Value::Float(number.round_to(digits, ctx.rounding()).unwrap_or(f64::NAN)))
+            }
+            Function::Floor(expr, _) => Ok(Value::Float(number(expr, ctx)?.floor())),
+            Function::Ceil(expr, _) => Ok(Value::Float(number(expr, ctx)?.ceil())),
+            Function::Trunc(expr, _) => Ok(Value::Float(number(expr, ctx)?.trunc())),
+            Function::Frac(expr, _) => Ok(Value::Float(number(expr, ctx)?.fract())),
+            Function::Deg(expr, _) => Ok(Value::Float(number(expr, ctx)?.to_degrees())),
+            Function::Rad(expr, _) => Ok(Value::Float(number(expr, ctx)?.to_radians())),
+            Function::Max(args, _) => {
+                let mut max = number(&args[0], ctx)?;
+                for arg in &args[1..] {
+                    max = max.max(number(arg, ctx)?);
                 }
-            },
-            Function::Round(expr, span) => Ok((expr.eval()?.0.round(), *span)),
-            Function::Floor(expr, span) => Ok((expr.eval()?.0.floor(), *span)),
-            Function::Ceil(expr, span) => Ok((expr.eval()?.0.ceil(), *span)),
-            Function::Trunc(expr, span) => Ok((expr.eval()?.0.trunc(), *span)),
-            Function::Frac(expr, span) => Ok((expr.eval()?.0.fract(), *span)),
-            Function::Deg(expr, span) => Ok((expr.eval()?.0.to_degrees(), *span)),
-            Function::Rad(expr, span) => Ok((expr.eval()?.0.to_radians(), *span)),
+                Ok(Value::Float(max))
+            }
+            Function::Min(args, _) => {
+                let mut min = number(&args[0], ctx)?;
+                for arg in &args[1..] {
+                    min = min.min(number(arg, ctx)?);
+                }
+                Ok(Value::Float(min))
+            }
+            Function::If(condition, then, otherwise, _) => {
+                let branch = if condition.eval_with(ctx)?.is_truthy() { then } else { otherwise };
+                branch.eval_with(ctx)
+            }
+            Function::Re(expr, _) | Function::Conj(expr, _) => Ok(Value::Float(number(expr, ctx)?)),
+            Function::Im(expr, _) => number(expr, ctx).map(|_| Value::Float(0.)),
+            Function::Arg(expr, _) => Ok(Value::Float(0f64.atan2(number(expr, ctx)?))),
+            Function::Custom(function, args, _) => {
+                let args = args
+                    .iter()
+                    .map(|arg| number(arg, ctx))
+                    .collect::<Result<Vec<f64>, Error>>()?;
+                Ok(Value::Float(function.call(&args)))
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+// Logarithm of `n` in `base`, with `log2` and `log10` for their bases.
+pub(crate) fn log_based(n: f64, base: f64) -> f64 {
+    match base {
+        two if (two - 2.).abs() <= f64::EPSILON => n.log2(),
+        ten if (ten - 10.).abs() <= f64::EPSILON => n.log10(),
+        base => n.log(base),
+    }
+}
+
+// Evaluate an argument which must be a number.
+fn number(expr: &Expr, ctx: &Context) -> Result<f64, Error> {
+    expr.eval_with(ctx)?.to_number(expr.span())
+}
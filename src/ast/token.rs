@@ -8,80 +8,178 @@ pub mod operator;
 
 use operator::Operator;
 
-use crate::maph_error::{Error, ErrorKinds};
+use crate::maph_error::{Error, ErrorKinds, Span};
 use crate::utils::DedupReplaceFor;
 
-const OPERATOR_CHARS: &[char] = &['+', '-', '*', '/', '%', '^', '~'];
-const OTHER_VALID_CHARS: &[char] = &['(', ')', '.', ',', ' '];
+const OPERATOR_CHARS: &[char] = &['+', '-', '*', '/', '%', '^', '~', '&', '|', '<', '>', '=', '±'];
+// Operators made of two chars, the first of them being a valid char on its own.
+const TWO_CHAR_OPERATORS: &[&str] = &["<<", ">>", "==", "!=", "<=", ">=", "&&", "||"];
+const OTHER_VALID_CHARS: &[char] = &['(', ')', '[', ']', ' ', '√', '!'];
+// Unicode operators and the ASCII operator they stand for.
+const OPERATOR_ALIASES: &[(char, char)] = &[('×', '*'), ('÷', '/'), ('−', '-')];
 
 pub type Calculation = Vec<Token>;
 
+/// How numbers and argument lists are written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberLocale {
+    pub decimal_separator: char,
+    pub list_separator: char,
+    /// Character allowed between the digits of a number, as in `1 000 000`.
+    pub grouping_separator: Option<char>,
+}
+
+impl NumberLocale {
+    /// `3.14` and `max(1, 2)`.
+    pub const POINT: Self = Self {
+        decimal_separator: '.',
+        list_separator: ',',
+        grouping_separator: None,
+    };
+    /// `3,14` and `max(1; 2)`.
+    pub const COMMA: Self = Self {
+        decimal_separator: ',',
+        list_separator: ';',
+        grouping_separator: None,
+    };
+
+    pub fn new(decimal_separator: char, list_separator: char, grouping_separator: Option<char>) -> Self {
+        Self {
+            decimal_separator,
+            list_separator,
+            grouping_separator,
+        }
+    }
+
+    #[inline]
+    fn is_valid(&self, c: &char) -> bool {
+        OPERATOR_CHARS.contains(c)
+            || OTHER_VALID_CHARS.contains(c)
+            || c.is_ascii_alphanumeric()
+            || *c == self.decimal_separator
+            || *c == self.list_separator
+            || OPERATOR_ALIASES.iter().any(|(alias, _)| alias == c)
+    }
+
+    // A grouping separator must sit between two digits of a number.
+    fn is_grouping(&self, input: &[(usize, char)], idx: usize) -> bool {
+        if Some(input[idx].1) != self.grouping_separator
+            || idx == 0
+            || !input.get(idx + 1).is_some_and(|(_, c)| c.is_ascii_digit())
+        {
+            return false;
+        }
+        let number_start = input[..idx]
+            .iter()
+            .rposition(|(_, c)| !(c.is_ascii_digit() || *c == self.decimal_separator || Some(*c) == self.grouping_separator))
+            .map_or(0, |i| i + 1);
+        number_start < idx && !(number_start > 0 && input[number_start - 1].1.is_ascii_alphabetic())
+    }
+}
+
+impl Default for NumberLocale {
+    fn default() -> Self {
+        Self::POINT
+    }
+}
+
 pub trait Tokenize {
     type Err;
-    fn tokenize(&self) -> Result<Calculation, Self::Err>;
+    fn tokenize_with(&self, locale: &NumberLocale) -> Result<Calculation, Self::Err>;
+
+    #[inline]
+    fn tokenize(&self) -> Result<Calculation, Self::Err> {
+        self.tokenize_with(&NumberLocale::default())
+    }
 }
 
 impl Tokenize for &str {
     type Err = Vec<Error>;
 
-    fn tokenize(&self) -> Result<Calculation, Self::Err> {
-        let input_len = self.len();
-
-        let mut input_vec = self
+    fn tokenize_with(&self, locale: &NumberLocale) -> Result<Calculation, Self::Err> {
+        let input_vec = self
             .trim_end()
-            .replace(",", ".")
-            .chars()
-            .enumerate()
+            .char_indices()
             .skip_while(|(_, c)| c.is_whitespace())
             .collect::<Vec<_>>();
+        let mut input_vec = (0..input_vec.len())
+            .filter(|idx| !locale.is_grouping(&input_vec, *idx))
+            .map(|idx| input_vec[idx])
+            .collect::<Vec<_>>();
         input_vec.dedup_by(|(_, a), (_, b)| a == b && a == &' ');
         input_vec.dedup_and_replace_for_by_key_then_build(&'/', 2, |(_, c)| c, |(i, _)|  (i - 1, '~'));
 
         let mut errors = input_vec
             .iter()
             .filter_map(|(i, c)| {
-                if !(OPERATOR_CHARS.contains(c)
-                    || OTHER_VALID_CHARS.contains(c)
-                    || c.is_ascii_alphanumeric())
-                {
-                    Some(Error::new(ErrorKinds::InvalideChar(*c), vec![*i]))
+                if !locale.is_valid(c) {
+                    Some(Error::new(ErrorKinds::InvalideChar(*c), vec![*i..*i + c.len_utf8()]))
                 } else {
                     None
                 }
             })
             .collect::<Vec<Error>>();
 
-        let mut buffer = String::new();
+        let mut buffer = vec![];
         let mut buffer_type = BufferType::None;
         let mut calculation = vec![];
 
-        for (index, c) in input_vec.into_iter().filter(|(_, c)| {
-            OPERATOR_CHARS.contains(c) || OTHER_VALID_CHARS.contains(c) || c.is_ascii_alphanumeric()
-        }) {
+        // Normalized chars with the span of the source char they come from.
+        let input_vec = input_vec
+            .into_iter()
+            .filter(|(_, c)| locale.is_valid(c))
+            .map(|(index, c)| {
+                let span = index..index + c.len_utf8();
+                let c = match c {
+                    c if c == locale.decimal_separator => '.',
+                    c if c == locale.list_separator => ',',
+                    c => OPERATOR_ALIASES
+                        .iter()
+                        .find(|(alias, _)| *alias == c)
+                        .map_or(c, |(_, op)| *op),
+                };
+                (span, c)
+            })
+            .collect::<Vec<_>>();
+
+        let mut skip_next = false;
+        for (pos, (span, c)) in input_vec.iter().cloned().enumerate() {
+            if skip_next {
+                skip_next = false;
+                continue;
+            }
+            let index = span.start;
             match c {
                 '0'..='9' | '.' => {
                     match buffer_type {
-                        BufferType::None => {
+                        // An exponent is an integer, `1e2.5` is not a single number.
+                        BufferType::Exponent | BufferType::Radix if c == '.' => {
+                            flush_buffer(&mut buffer, &mut calculation, &mut errors);
                             buffer_type = BufferType::Number;
                         }
+                        BufferType::None => buffer_type = BufferType::Number,
                         _ => {}
                     }
-                    buffer.push(c);
+                    buffer.push((span, c));
+                }
+                'e' | 'E' if matches!(buffer_type, BufferType::Number) && is_exponent(&input_vec[pos + 1..]) => {
+                    buffer_type = BufferType::Exponent;
+                    buffer.push((span, c));
+                }
+                'x' | 'b' | 'o' if matches!(buffer[..], [(_, '0')]) && is_radix_literal(c, &input_vec[pos + 1..]) => {
+                    buffer_type = BufferType::Radix;
+                    buffer.push((span, c));
+                }
+                'a'..='z' | 'A'..='Z' if matches!(buffer_type, BufferType::Radix) => {
+                    buffer.push((span, c));
+                }
+                '+' | '-' if matches!(buffer_type, BufferType::Exponent) && matches!(buffer.last(), Some((_, 'e' | 'E'))) => {
+                    buffer.push((span, c));
                 }
                 'a'..='z' | 'A'..='Z' => {
                     match buffer_type {
-                        BufferType::Number => {
-                            match TokenKind::from_str(&buffer) {
-                                Ok(tk) => {
-                                    calculation.push(Token::new(tk, index - buffer.len()));
-                                    buffer.clear();
-                                }
-                                Err(mut e) => {
-                                    e.add_span(index - 1);
-                                    errors.push(e);
-                                    buffer.clear();
-                                }
-                            }
+                        BufferType::Number | BufferType::Exponent | BufferType::Radix => {
+                            flush_buffer(&mut buffer, &mut calculation, &mut errors);
                             buffer_type = BufferType::Ident;
                         }
                         BufferType::None => {
@@ -89,49 +187,41 @@ impl Tokenize for &str {
                         }
                         _ => {}
                     }
-                    buffer.push(c);
+                    buffer.push((span, c));
                 }
-                c if OPERATOR_CHARS.contains(&c) || OTHER_VALID_CHARS.contains(&c) => {
-                    match buffer_type {
-                        BufferType::Ident | BufferType::Number => {
-                            match TokenKind::from_str(&buffer) {
-                                Ok(tk) => {
-                                    calculation.push(Token::new(tk, index - buffer.len()));
-                                    buffer.clear();
-                                }
-                                Err(mut e) => {
-                                    e.add_span(index - 1);
-                                    errors.push(e);
-                                    buffer.clear();
-                                }
-                            }
-                            buffer_type = BufferType::None;
-                        }
-                        _ => {}
+                c if OPERATOR_CHARS.contains(&c) || OTHER_VALID_CHARS.contains(&c) || c == ',' => {
+                    if let BufferType::Ident | BufferType::Number | BufferType::Exponent | BufferType::Radix = buffer_type {
+                        flush_buffer(&mut buffer, &mut calculation, &mut errors);
+                        buffer_type = BufferType::None;
                     }
                     if c == ' ' {
                         continue;
                     }
-                    match TokenKind::from_str(&c.to_string()) {
-                        Ok(tk) => calculation.push(Token::new(tk, index)),
-                        Err(e) => errors.push(e),
+                    let mut symbol = c.to_string();
+                    let mut span = span;
+                    match input_vec.get(pos + 1) {
+                        Some((next_span, next)) if TWO_CHAR_OPERATORS.contains(&format!("{}{}", c, next).as_str()) => {
+                            symbol.push(*next);
+                            span = span.start..next_span.end;
+                            skip_next = true;
+                        }
+                        // A `~` may stand for the `//` it replaced.
+                        _ if c == '~' && self[index..].starts_with("//") => span = index..index + 2,
+                        _ => {}
+                    }
+                    match TokenKind::from_str(&symbol) {
+                        Ok(tk) => calculation.push(Token::new(tk, span)),
+                        Err(mut e) => {
+                            e.span = vec![span];
+                            errors.push(e)
+                        }
                     }
                 }
                 _ => unreachable!(),
             }
         }
 
-        if !matches!(buffer_type, BufferType::None) {
-            match TokenKind::from_str(&buffer) {
-                Ok(tk) => {
-                    calculation.push(Token::new(tk, input_len - buffer.len()));
-                }
-                Err(mut e) => {
-                    e.add_span(input_len - 1);
-                    errors.push(e);
-                }
-            }
-        }
+        flush_buffer(&mut buffer, &mut calculation, &mut errors);
 
         if errors.is_empty() {
             Ok(calculation)
@@ -141,25 +231,85 @@ impl Tokenize for &str {
     }
 }
 
+// Whether the chars following a `0x`, `0b` or `0o` prefix make it an integer literal.
+fn is_radix_literal(prefix: char, next: &[(Span, char)]) -> bool {
+    match next.first() {
+        Some((_, d)) => radix(prefix).is_some_and(|radix| d.is_digit(radix)),
+        None => false,
+    }
+}
+
+fn radix(prefix: char) -> Option<u32> {
+    match prefix {
+        'x' => Some(16),
+        'b' => Some(2),
+        'o' => Some(8),
+        _ => None,
+    }
+}
+
+// Whether the chars following an `e` make it an exponent, as in `5e-2`.
+fn is_exponent(next: &[(Span, char)]) -> bool {
+    match next {
+        [(_, '+' | '-'), (_, d), ..] | [(_, d), ..] => d.is_ascii_digit(),
+        [] => false,
+    }
+}
+
+// Turn the buffered number or identifier into a token.
+fn flush_buffer(buffer: &mut Vec<(Span, char)>, calculation: &mut Calculation, errors: &mut Vec<Error>) {
+    let span = match (buffer.first(), buffer.last()) {
+        (Some((first, _)), Some((last, _))) => first.start..last.end,
+        _ => return,
+    };
+    let word = buffer.iter().map(|(_, c)| c).collect::<String>();
+    match TokenKind::from_str(&word) {
+        Ok(tk) => calculation.push(Token::new(tk, span)),
+        Err(mut e) => {
+            // `TokenKind::from_str` spans are relative to the word, if any.
+            for span in e.span.iter_mut() {
+                *span = buffer[span.start].0.start..buffer[span.end - 1].0.end;
+            }
+            if e.span.is_empty() {
+                e.span.push(span);
+            }
+            errors.push(e);
+        }
+    }
+    buffer.clear();
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Token {
     pub token_kind: TokenKind,
-    pub span: usize,
+    pub span: Span,
 }
 
 impl Token {
-    pub fn new(token_kind: TokenKind, span: usize) -> Self {
+    pub fn new(token_kind: TokenKind, span: Span) -> Self {
         Self { token_kind, span }
     }
 }
 
 #[derive(Debug, PartialEq)]
 pub enum TokenKind {
-    Number(f64),
+    /// A float, with the digits of its literal when the float does not hold
+    /// them all, see [`Numeric::from_decimal`](crate::Numeric::from_decimal).
+    Number(f64, Option<String>),
+    Int(i64),
     Op(Operator),
     Ident(String),
     LBrack,
     RBrack,
+    /// `[`, opening an interval `[lo, hi]`.
+    LSquare,
+    /// `]`, closing an interval.
+    RSquare,
+    Separator,
+    /// `√`, the square root of the following operand.
+    Root,
+    /// `!`, the bitwise not of the following operand.
+    Not,
 }
 
 impl FromStr for TokenKind {
@@ -170,24 +320,43 @@ impl FromStr for TokenKind {
             static ref RE_IDENT: Regex = Regex::new(r#"^((?i)[a-z])((?i)[a-z0-9])*$"#).unwrap();
         };
         let commas = s
-            .chars()
+            .char_indices()
             .rev()
-            .enumerate()
-            .filter_map(|(i, c)| if c == '.' { Some(i) } else { None })
-            .collect::<Vec<usize>>();
+            .filter_map(|(i, c)| if c == '.' { Some(i..i + 1) } else { None })
+            .collect::<Vec<Span>>();
         if commas.len() > 1 {
             return Err(Error::new(ErrorKinds::MultipleComma, commas));
         }
 
+        if let Some(radix) = s.strip_prefix('0').and_then(|s| s.chars().next()).and_then(radix) {
+            let digits = &s[2..];
+            if let Some((i, c)) = digits.char_indices().find(|(_, c)| !c.is_digit(radix)) {
+                return Err(Error::new(ErrorKinds::InvalideChar(c), vec![i + 2..i + 3]));
+            }
+            return i64::from_str_radix(digits, radix)
+                .map(Self::Int)
+                .map_err(|_| Error::new(ErrorKinds::NotInRange(i64::MIN as f64, i64::MAX as f64), vec![0..s.len()]));
+        }
+
+        let int = s.parse::<i64>();
         let parsed = s.parse::<f64>();
+        let operator = Operator::from_str(s);
         match s {
-            _ if parsed.is_ok() => Ok(Self::Number(parsed.unwrap())),
-            i if RE_IDENT.is_match(s) => Ok(Self::Ident(i.to_owned())),
-            op if OPERATOR_CHARS.contains(&s.chars().next().unwrap_or_default()) => {
-                Ok(Self::Op(Operator::from_str(op).unwrap()))
+            _ if int.is_ok() => Ok(Self::Int(int.unwrap())),
+            // `inf` or `nan` are names, not floats.
+            _ if parsed.is_ok() && s.starts_with(|c: char| c.is_ascii_digit() || c == '.') => {
+                let n = parsed.unwrap();
+                Ok(Self::Number(n, exact_digits(s, n)))
             }
+            _ if operator.is_ok() => Ok(Self::Op(operator.unwrap())),
+            i if RE_IDENT.is_match(s) => Ok(Self::Ident(i.to_owned())),
             "(" => Ok(Self::LBrack),
             ")" => Ok(Self::RBrack),
+            "[" => Ok(Self::LSquare),
+            "]" => Ok(Self::RSquare),
+            "," => Ok(Self::Separator),
+            "√" => Ok(Self::Root),
+            "!" => Ok(Self::Not),
             other => Err(Error::new(
                 ErrorKinds::UnknowOperator(other.to_owned()),
                 vec![],
@@ -196,28 +365,52 @@ impl FromStr for TokenKind {
     }
 }
 
+// The literal `s` of the float `n`, when it has more significant digits than
+// a float holds or is out of its range.
+fn exact_digits(s: &str, n: f64) -> Option<String> {
+    let mantissa = s.split(['e', 'E']).next().unwrap_or(s);
+    let significant = mantissa
+        .trim_start_matches(['0', '.'])
+        .trim_end_matches(['0', '.'])
+        .chars()
+        .filter(char::is_ascii_digit)
+        .count();
+    if significant > f64::DIGITS as usize || (!n.is_normal() && significant > 0) {
+        Some(s.to_owned())
+    } else {
+        None
+    }
+}
+
 enum BufferType {
     Ident,
     Number,
+    /// A number followed by its exponent suffix.
+    Exponent,
+    /// An integer literal with a `0x`, `0b` or `0o` prefix.
+    Radix,
     None,
 }
 
-#[macro_export]
+#[cfg(test)]
 macro_rules! token {
     ($tk_kind:ident $( ( $($enum_data:expr),* ) )?, $span:expr) => {
-        crate::ast::token::Token {
-            token_kind: crate::ast::token::TokenKind::$tk_kind$( ( $($enum_data)* ) )?,
+        $crate::ast::token::Token {
+            token_kind: $crate::ast::token::TokenKind::$tk_kind$( ( $($enum_data),* ) )?,
             span: $span
         }
     };
     (Op::$op_kind:ident, $span:expr) => {
-        crate::ast::token::Token {
-            token_kind: crate::ast::token::TokenKind::Op(Operator::$op_kind),
+        $crate::ast::token::Token {
+            token_kind: $crate::ast::token::TokenKind::Op($crate::ast::token::operator::Operator::$op_kind),
             span: $span
         }
     }
 }
 
+#[cfg(test)]
+pub(crate) use token;
+
 #[cfg(test)]
 mod tests {
     use std::vec;
@@ -229,7 +422,7 @@ mod tests {
         let calculation = "$".tokenize();
         assert_eq!(
             calculation,
-            Err(vec![Error::new(ErrorKinds::InvalideChar('$'), vec![0])])
+            Err(vec![Error::new(ErrorKinds::InvalideChar('$'), vec![0..1])])
         )
     }
 
@@ -239,9 +432,9 @@ mod tests {
         assert_eq!(
             calculation,
             Err(vec![
-                Error::new(ErrorKinds::InvalideChar('$'), vec![0]),
-                Error::new(ErrorKinds::InvalideChar('¨'), vec![1]),
-                Error::new(ErrorKinds::InvalideChar('"'), vec![2]),
+                Error::new(ErrorKinds::InvalideChar('$'), vec![0..1]),
+                Error::new(ErrorKinds::InvalideChar('¨'), vec![1..3]),
+                Error::new(ErrorKinds::InvalideChar('"'), vec![3..4]),
             ])
         )
     }
@@ -252,9 +445,9 @@ mod tests {
         assert_eq!(
             calculation,
             Ok(vec![
-                token!(Number(1.), 0),
-                token!(Op::Add, 2),
-                token!(Number(2.), 4),
+                token!(Int(1), 0..1),
+                token!(Op::Add, 2..3),
+                token!(Int(2), 4..5),
             ])
         );
     }
@@ -265,21 +458,38 @@ mod tests {
         assert_eq!(
             calcuation,
             Ok(vec![
-                token!(LBrack, 0),
-                token!(Number(2.), 1),
-                token!(Op::Sub, 3),
-                token!(LBrack, 5),
-                token!(LBrack, 6),
-                token!(Number(0.), 7),
-                token!(RBrack, 8),
-                token!(Op::Mul, 10),
-                token!(Number(2.), 12),
-                token!(RBrack, 13),
-                token!(RBrack, 14),
-                token!(LBrack, 15),
-                token!(RBrack, 16),
-                token!(LBrack, 17),
-                token!(LBrack, 18),
+                token!(LBrack, 0..1),
+                token!(Int(2), 1..2),
+                token!(Op::Sub, 3..4),
+                token!(LBrack, 5..6),
+                token!(LBrack, 6..7),
+                token!(Int(0), 7..8),
+                token!(RBrack, 8..9),
+                token!(Op::Mul, 10..11),
+                token!(Int(2), 12..13),
+                token!(RBrack, 13..14),
+                token!(RBrack, 14..15),
+                token!(LBrack, 15..16),
+                token!(RBrack, 16..17),
+                token!(LBrack, 17..18),
+                token!(LBrack, 18..19),
+            ])
+        )
+    }
+
+    #[test]
+    fn tokenize_interval() {
+        let calculation = "[1, 2]±0.5".tokenize();
+        assert_eq!(
+            calculation,
+            Ok(vec![
+                token!(LSquare, 0..1),
+                token!(Int(1), 1..2),
+                token!(Separator, 2..3),
+                token!(Int(2), 4..5),
+                token!(RSquare, 5..6),
+                token!(Op::PlusMinus, 6..8),
+                token!(Number(0.5, None), 8..11),
             ])
         )
     }
@@ -287,13 +497,26 @@ mod tests {
     #[test]
     fn tokenize_float_number() {
         let calculation = "42.69".tokenize();
-        assert_eq!(calculation, Ok(vec![token!(Number(42.69), 0)]))
+        assert_eq!(calculation, Ok(vec![token!(Number(42.69, None), 0..5)]))
     }
 
     #[test]
     fn tokenize_float_number_without_decimal_part() {
         let calculation = "13105.".tokenize();
-        assert_eq!(calculation, Ok(vec![token!(Number(13105.), 0)]))
+        assert_eq!(calculation, Ok(vec![token!(Number(13105., None), 0..6)]))
+    }
+
+    #[test]
+    fn tokenize_long_float_number() {
+        let digits = "0.1234567890123456789012345";
+        let calculation = format!("{} + 1e-400 + 0.12345678901234500", digits).as_str().tokenize();
+        assert_eq!(calculation, Ok(vec![
+            token!(Number(0.12345678901234568, Some(digits.to_owned())), 0..27),
+            token!(Op::Add, 28..29),
+            token!(Number(0., Some("1e-400".to_owned())), 30..36),
+            token!(Op::Add, 37..38),
+            token!(Number(0.123456789012345, None), 39..58),
+        ]));
     }
 
     #[test]
@@ -301,7 +524,7 @@ mod tests {
         let calculation = "131.05.".tokenize();
         assert_eq!(
             calculation,
-            Err(vec![Error::new(ErrorKinds::MultipleComma, vec![6, 3])])
+            Err(vec![Error::new(ErrorKinds::MultipleComma, vec![6..7, 3..4])])
         )
     }
 
@@ -310,7 +533,7 @@ mod tests {
         let calculation = "150 131.05.".tokenize();
         assert_eq!(
             calculation,
-            Err(vec![Error::new(ErrorKinds::MultipleComma, vec![10, 7])])
+            Err(vec![Error::new(ErrorKinds::MultipleComma, vec![10..11, 7..8])])
         )
     }
 
@@ -319,7 +542,7 @@ mod tests {
         let calculation = "131.05. 150".tokenize();
         assert_eq!(
             calculation,
-            Err(vec![Error::new(ErrorKinds::MultipleComma, vec![6, 3])])
+            Err(vec![Error::new(ErrorKinds::MultipleComma, vec![6..7, 3..4])])
         )
     }
 
@@ -329,18 +552,120 @@ mod tests {
         assert_eq!(
             calculation,
             Err(vec![
-                Error::new(ErrorKinds::MultipleComma, vec![6, 3]),
-                Error::new(ErrorKinds::MultipleComma, vec![18, 15, 12]),
+                Error::new(ErrorKinds::MultipleComma, vec![6..7, 3..4]),
+                Error::new(ErrorKinds::MultipleComma, vec![18..19, 15..16, 12..13]),
             ])
         )
     }
 
+    #[test]
+    fn tokenize_separator() {
+        let calculation = "max(1.5, 2)".tokenize();
+        assert_eq!(
+            calculation,
+            Ok(vec![
+                token!(Ident("max".to_owned()), 0..3),
+                token!(LBrack, 3..4),
+                token!(Number(1.5, None), 4..7),
+                token!(Separator, 7..8),
+                token!(Int(2), 9..10),
+                token!(RBrack, 10..11),
+            ])
+        )
+    }
+
+    #[test]
+    fn tokenize_comma_locale() {
+        let calculation = "max(2,71; 2)".tokenize_with(&NumberLocale::COMMA);
+        assert_eq!(
+            calculation,
+            Ok(vec![
+                token!(Ident("max".to_owned()), 0..3),
+                token!(LBrack, 3..4),
+                token!(Number(2.71, None), 4..8),
+                token!(Separator, 8..9),
+                token!(Int(2), 10..11),
+                token!(RBrack, 11..12),
+            ])
+        );
+        let calculation = "3.14".tokenize_with(&NumberLocale::COMMA);
+        assert_eq!(
+            calculation,
+            Err(vec![Error::new(ErrorKinds::InvalideChar('.'), vec![1..2])])
+        );
+    }
+
+    #[test]
+    fn tokenize_grouping_separator() {
+        let locale = NumberLocale::new(',', ';', Some(' '));
+        let calculation = "1 000 000,5 + x2 3".tokenize_with(&locale);
+        assert_eq!(
+            calculation,
+            Ok(vec![
+                token!(Number(1000000.5, None), 0..11),
+                token!(Op::Add, 12..13),
+                token!(Ident("x2".to_owned()), 14..16),
+                token!(Int(3), 17..18),
+            ])
+        );
+        let locale = NumberLocale::new('.', ',', Some('_'));
+        let calculation = "1_000.5 _2".tokenize_with(&locale);
+        assert_eq!(
+            calculation,
+            Err(vec![Error::new(ErrorKinds::InvalideChar('_'), vec![8..9])])
+        );
+    }
+
+    #[test]
+    fn tokenize_multiple_comma_with_grouping_separator() {
+        let locale = NumberLocale::new(',', ';', Some(' '));
+        let calculation = "2 + 1 000,5,3".tokenize_with(&locale);
+        assert_eq!(
+            calculation,
+            Err(vec![Error::new(ErrorKinds::MultipleComma, vec![11..12, 9..10])])
+        )
+    }
+
+    #[test]
+    fn tokenize_byte_spans() {
+        let calculation = "é + 1".tokenize();
+        assert_eq!(
+            calculation,
+            Err(vec![Error::new(ErrorKinds::InvalideChar('é'), vec![0..2])])
+        );
+        let calculation = " 2 ×  10 ".tokenize();
+        assert_eq!(
+            calculation,
+            Ok(vec![
+                token!(Int(2), 1..2),
+                token!(Op::Mul, 3..5),
+                token!(Int(10), 7..9),
+            ])
+        );
+    }
+
+    #[test]
+    fn tokenize_unicode_operators() {
+        let calculation = "√4 ÷ 2 − 1".tokenize();
+        assert_eq!(
+            calculation,
+            Ok(vec![
+                token!(Root, 0..3),
+                token!(Int(4), 3..4),
+                token!(Op::Div, 5..7),
+                token!(Int(2), 8..9),
+                token!(Op::Sub, 10..13),
+                token!(Int(1), 14..15),
+            ])
+        );
+    }
+
     #[test]
     fn tokenize_multiple_number() {
         let caluctation = "177 013".tokenize();
         assert_eq!(
             caluctation,
-            Ok(vec![token!(Number(177.), 0), token!(Number(13.), 4)])
+            Ok(vec![token!(Int(177), 0..3), token!(Int(13), 4..7)])
         );
     }
 
@@ -350,9 +675,9 @@ mod tests {
         assert_eq!(
             calculation,
             Ok(vec![
-                token!(Number(2.), 0),
-                token!(Op::FDiv, 2),
-                token!(Number(3.), 5),
+                token!(Int(2), 0..1),
+                token!(Op::FDiv, 2..4),
+                token!(Int(3), 5..6),
             ])
         )
     }
@@ -363,10 +688,10 @@ mod tests {
         assert_eq!(
             calculation,
             Ok(vec![
-                token!(Number(2.), 0),
-                token!(Op::FDiv, 2),
-                token!(Op::Div, 4),
-                token!(Number(3.), 6),
+                token!(Int(2), 0..1),
+                token!(Op::FDiv, 2..4),
+                token!(Op::Div, 4..5),
+                token!(Int(3), 6..7),
             ])
         )
     }
@@ -377,14 +702,14 @@ mod tests {
         assert_eq!(
             calculation,
             Ok(vec![
-                token!(Op::Div, 0),
-                token!(Op::Mul, 2),
-                token!(Op::Sub, 4),
-                token!(Op::Add, 6),
-                token!(Op::Mod, 8),
-                token!(Op::FDiv, 10),
-                token!(Op::Pow, 12),
-                token!(Op::FDiv, 14),
+                token!(Op::Div, 0..1),
+                token!(Op::Mul, 2..3),
+                token!(Op::Sub, 4..5),
+                token!(Op::Add, 6..7),
+                token!(Op::Mod, 8..9),
+                token!(Op::FDiv, 10..11),
+                token!(Op::Pow, 12..13),
+                token!(Op::FDiv, 14..16),
             ])
         )
     }
@@ -395,15 +720,15 @@ mod tests {
         assert_eq!(
             calculation,
             Ok(vec![
-                token!(Ident("sin452".to_owned()), 0),
-                token!(Op::FDiv, 6),
-                token!(Number(2.), 7),
-                token!(Ident("aB".to_owned()), 8),
-                token!(Ident("cd".to_owned()), 11),
-                token!(Op::Add, 13),
-                token!(Ident("PI".to_owned()), 14),
-                token!(Op::Sub, 16),
-                token!(Number(8.), 17),
+                token!(Ident("sin452".to_owned()), 0..6),
+                token!(Op::FDiv, 6..7),
+                token!(Int(2), 7..8),
+                token!(Ident("aB".to_owned()), 8..10),
+                token!(Ident("cd".to_owned()), 11..13),
+                token!(Op::Add, 13..14),
+                token!(Ident("PI".to_owned()), 14..16),
+                token!(Op::Sub, 16..17),
+                token!(Int(8), 17..18),
             ])
         )
     }
@@ -412,26 +737,117 @@ mod tests {
     fn tokenize_complex_calculation() {
         let calculation = "sin(5E-2) - 1/2(366^3) // 31 ~ 5".tokenize();
         assert_eq!(calculation, Ok(vec![
-            token!(Ident("sin".to_owned()), 0),
-            token!(LBrack, 3),
-            token!(Number(5.), 4),
-            token!(Ident("E".to_owned()), 5),
-            token!(Op::Sub, 6),
-            token!(Number(2.), 7),
-            token!(RBrack, 8),
-            token!(Op::Sub, 10),
-            token!(Number(1.), 12),
-            token!(Op::Div, 13),
-            token!(Number(2.), 14),
-            token!(LBrack, 15),
-            token!(Number(366.), 16),
-            token!(Op::Pow, 19),
-            token!(Number(3.), 20),
-            token!(RBrack, 21),
-            token!(Op::FDiv, 23),
-            token!(Number(31.), 26),
-            token!(Op::FDiv, 29),
-            token!(Number(5.), 31),
+            token!(Ident("sin".to_owned()), 0..3),
+            token!(LBrack, 3..4),
+            token!(Number(5E-2, None), 4..8),
+            token!(RBrack, 8..9),
+            token!(Op::Sub, 10..11),
+            token!(Int(1), 12..13),
+            token!(Op::Div, 13..14),
+            token!(Int(2), 14..15),
+            token!(LBrack, 15..16),
+            token!(Int(366), 16..19),
+            token!(Op::Pow, 19..20),
+            token!(Int(3), 20..21),
+            token!(RBrack, 21..22),
+            token!(Op::FDiv, 23..25),
+            token!(Int(31), 26..28),
+            token!(Op::FDiv, 29..30),
+            token!(Int(5), 31..32),
+        ]));
+    }
+
+    #[test]
+    fn tokenize_scientific_notation() {
+        let calculation = "1.2e10 + 5E+2 − 3e−1".tokenize();
+        assert_eq!(calculation, Ok(vec![
+            token!(Number(1.2e10, None), 0..6),
+            token!(Op::Add, 7..8),
+            token!(Number(5E2, None), 9..13),
+            token!(Op::Sub, 14..17),
+            token!(Number(3e-1, None), 18..24),
+        ]));
+    }
+
+    #[test]
+    fn tokenize_exponent_and_const_e() {
+        let calculation = "2e + e^2 - 3e-x".tokenize();
+        assert_eq!(calculation, Ok(vec![
+            token!(Int(2), 0..1),
+            token!(Ident("e".to_owned()), 1..2),
+            token!(Op::Add, 3..4),
+            token!(Ident("e".to_owned()), 5..6),
+            token!(Op::Pow, 6..7),
+            token!(Int(2), 7..8),
+            token!(Op::Sub, 9..10),
+            token!(Int(3), 11..12),
+            token!(Ident("e".to_owned()), 12..13),
+            token!(Op::Sub, 13..14),
+            token!(Ident("x".to_owned()), 14..15),
+        ]));
+    }
+
+    #[test]
+    fn tokenize_radix_literals_and_bitwise_operators() {
+        let calculation = "0xFF & 0b1010 | 0o17 << 2 >> !x xor 1".tokenize();
+        assert_eq!(calculation, Ok(vec![
+            token!(Int(255), 0..4),
+            token!(Op::BitAnd, 5..6),
+            token!(Int(10), 7..13),
+            token!(Op::BitOr, 14..15),
+            token!(Int(15), 16..20),
+            token!(Op::Shl, 21..23),
+            token!(Int(2), 24..25),
+            token!(Op::Shr, 26..28),
+            token!(Not, 29..30),
+            token!(Ident("x".to_owned()), 30..31),
+            token!(Op::Xor, 32..35),
+            token!(Int(1), 36..37),
+        ]));
+    }
+
+    #[test]
+    fn tokenize_invalid_radix_literal() {
+        let calculation = "0b102 + 0x = 1".tokenize();
+        assert_eq!(calculation, Err(vec![
+            Error::new(ErrorKinds::InvalideChar('2'), vec![4..5]),
+            Error::new(ErrorKinds::UnknowOperator("=".to_owned()), vec![11..12]),
+        ]));
+    }
+
+    #[test]
+    fn tokenize_unknow_word() {
+        let calculation = "1 + x2.5".tokenize();
+        assert_eq!(
+            calculation,
+            Err(vec![Error::new(ErrorKinds::UnknowOperator("x2.5".to_owned()), vec![4..8])])
+        );
+    }
+
+    #[test]
+    fn tokenize_comparison_and_logical_operators() {
+        let calculation = "a>=1&&!(b<2)||c!=d==e<=f>g".tokenize();
+        assert_eq!(calculation, Ok(vec![
+            token!(Ident("a".to_owned()), 0..1),
+            token!(Op::Ge, 1..3),
+            token!(Int(1), 3..4),
+            token!(Op::And, 4..6),
+            token!(Not, 6..7),
+            token!(LBrack, 7..8),
+            token!(Ident("b".to_owned()), 8..9),
+            token!(Op::Lt, 9..10),
+            token!(Int(2), 10..11),
+            token!(RBrack, 11..12),
+            token!(Op::Or, 12..14),
+            token!(Ident("c".to_owned()), 14..15),
+            token!(Op::Ne, 15..17),
+            token!(Ident("d".to_owned()), 17..18),
+            token!(Op::Eq, 18..20),
+            token!(Ident("e".to_owned()), 20..21),
+            token!(Op::Le, 21..23),
+            token!(Ident("f".to_owned()), 23..24),
+            token!(Op::Gt, 24..25),
+            token!(Ident("g".to_owned()), 25..26),
         ]));
     }
 }
@@ -1,3 +1,4 @@
+use std::fmt;
 use std::str::FromStr;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -9,6 +10,60 @@ pub enum Operator {
     Pow,  //  ^
     Mod,  //  %
     FDiv, //  //
+    BitAnd, //  &
+    BitOr,  //  |
+    Xor,    //  xor
+    Shl,    //  <<
+    Shr,    //  >>
+    Eq,     //  ==
+    Ne,     //  !=
+    Lt,     //  <
+    Le,     //  <=
+    Gt,     //  >
+    Ge,     //  >=
+    And,    //  &&
+    Or,     //  ||
+    PlusMinus, //  ±
+}
+
+impl Operator {
+    /// Binding power of the operator, higher binds tighter.
+    /// `11` is left to implicit multiplication, see [`Context::set_implicit_multiplication`].
+    ///
+    /// [`Context::set_implicit_multiplication`]: crate::Context::set_implicit_multiplication
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Self::Or => 1,
+            Self::And => 2,
+            Self::Eq | Self::Ne | Self::Lt | Self::Le | Self::Gt | Self::Ge => 3,
+            Self::BitOr => 4,
+            Self::Xor => 5,
+            Self::BitAnd => 6,
+            Self::Shl | Self::Shr => 7,
+            Self::Add | Self::Sub => 8,
+            Self::Mul | Self::Div | Self::Mod | Self::FDiv => 9,
+            // `2 * 5±0.1` is `2 * (5±0.1)`.
+            Self::PlusMinus => 10,
+            Self::Pow => 12,
+        }
+    }
+
+    /// Whether the operator gives a boolean, `1` for true and `0` for false.
+    #[inline]
+    pub fn is_boolean(&self) -> bool {
+        matches!(self, Self::Eq | Self::Ne | Self::Lt | Self::Le | Self::Gt | Self::Ge | Self::And | Self::Or)
+    }
+
+    /// Whether the operator only applies to integers.
+    #[inline]
+    pub fn is_bitwise(&self) -> bool {
+        matches!(self, Self::BitAnd | Self::BitOr | Self::Xor | Self::Shl | Self::Shr)
+    }
+
+    #[inline]
+    pub fn is_right_associative(&self) -> bool {
+        matches!(self, Self::Pow)
+    }
 }
 
 impl FromStr for Operator {
@@ -23,10 +78,70 @@ impl FromStr for Operator {
             "^" => Ok(Self::Pow),
             "%" => Ok(Self::Mod),
             "//" | "~" => Ok(Self::FDiv),
+            "&" => Ok(Self::BitAnd),
+            "|" => Ok(Self::BitOr),
+            "<<" => Ok(Self::Shl),
+            ">>" => Ok(Self::Shr),
+            xor if xor.eq_ignore_ascii_case("xor") => Ok(Self::Xor),
+            "==" => Ok(Self::Eq),
+            "!=" => Ok(Self::Ne),
+            "<" => Ok(Self::Lt),
+            "<=" => Ok(Self::Le),
+            ">" => Ok(Self::Gt),
+            ">=" => Ok(Self::Ge),
+            "&&" => Ok(Self::And),
+            "||" => Ok(Self::Or),
+            "±" => Ok(Self::PlusMinus),
             _ => Err(format!(
                 "Try to create an Operator from an unkwon &str : `{}`",
                 s
             )),
         }
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Pow => "^",
+            Self::Mod => "%",
+            Self::FDiv => "//",
+            Self::BitAnd => "&",
+            Self::BitOr => "|",
+            Self::Xor => "xor",
+            Self::Shl => "<<",
+            Self::Shr => ">>",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::And => "&&",
+            Self::Or => "||",
+            Self::PlusMinus => "±",
+        };
+        f.write_str(symbol)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum UnaryOp {
+    Neg,  //  -
+    Plus, //  +
+    Not,  //  !
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Neg => f.write_str("-"),
+            Self::Plus => f.write_str("+"),
+            Self::Not => f.write_str("!"),
+        }
+    }
+}
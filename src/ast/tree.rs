@@ -1,55 +1,162 @@
 
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::context::Context;
 use crate::eval::Eval;
 use crate::maph_error::{
     Error,
-    ErrorKinds
+    ErrorKinds,
+    Span
 };
+use crate::value::Value;
 
 use super::expr::Expr;
 use super::token::operator::Operator;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TreeNode {
     pub l_expr: Expr,
     pub op: Operator,
     pub r_expr: Expr,
+    pub span: Span,
+}
+
+impl TreeNode {
+    pub fn new(l_expr: Expr, op: Operator, r_expr: Expr, span: Span) -> Self {
+        Self { l_expr, op, r_expr, span }
+    }
+}
+
+/// Infix form with brackets only around the operands binding less tightly
+/// than the operator, so `(1 + 2) * 3 - 4` prints as written.
+impl fmt::Display for TreeNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precedence = self.op.precedence();
+        // An operand of the same precedence is on the side of the associativity.
+        let (l_min, r_min) = if self.op.is_right_associative() {
+            (precedence + 1, precedence)
+        } else {
+            (precedence, precedence + 1)
+        };
+        self.l_expr.fmt_operand(f, l_min)?;
+        write!(f, " {} ", self.op)?;
+        self.r_expr.fmt_operand(f, r_min)
+    }
 }
 
 impl Eval for TreeNode {
-    type Output = (f64, usize);
+    type Output = Value;
     type Err = Error;
 
-    fn eval(&self) -> Result<Self::Output, Self::Err> {
-        let l_number = self.l_expr.eval()?;
-        let r_number = self.r_expr.eval()?;
+    fn eval_with(&self, ctx: &Context) -> Result<Self::Output, Self::Err> {
+        let l_value = self.l_expr.eval_with(ctx)?;
+        // `&&` and `||` only evaluate their right operand when needed.
         match self.op {
-            Operator::Add => {
-                Ok((l_number.0 + r_number.0, l_number.1))
+            Operator::And if !l_value.is_truthy() => return Ok(Value::Bool(false)),
+            Operator::Or if l_value.is_truthy() => return Ok(Value::Bool(true)),
+            Operator::And | Operator::Or => return Ok(Value::Bool(self.r_expr.eval_with(ctx)?.is_truthy())),
+            Operator::PlusMinus => {
+                return Err(Error::new(ErrorKinds::Unsupported(self.op.to_string()), vec![self.span.clone()]))
             }
-            Operator::Sub => {
-                Ok((l_number.0 - r_number.0, l_number.1))
+            _ => {}
+        }
+        let r_value = self.r_expr.eval_with(ctx)?;
+        let (l_span, r_span) = (self.l_expr.span(), self.r_expr.span());
+        let mismatch = || {
+            Error::new(
+                ErrorKinds::TypeMismatch(l_value.value_type(), r_value.value_type()),
+                vec![l_span.clone(), r_span.clone()],
+            )
+        };
+        match (l_value, r_value) {
+            (Value::Bool(l), Value::Bool(r)) => match self.op {
+                Operator::Eq => Ok(Value::Bool(l == r)),
+                Operator::Ne => Ok(Value::Bool(l != r)),
+                Operator::BitAnd => Ok(Value::Bool(l & r)),
+                Operator::BitOr => Ok(Value::Bool(l | r)),
+                Operator::Xor => Ok(Value::Bool(l ^ r)),
+                _ => Err(mismatch()),
+            },
+            (Value::Bool(_), _) | (_, Value::Bool(_)) => Err(mismatch()),
+            (Value::Int(l), Value::Int(r)) => self.eval_int(l, r),
+            _ if self.op.is_bitwise() => self.eval_int(l_value.to_integer(l_span)?, r_value.to_integer(r_span)?),
+            (l, r) => self.eval_float(l.to_number(l_span)?, r.to_number(r_span)?),
+        }
+    }
+}
+
+impl TreeNode {
+    // Integer arithmetic, falling back to floats on overflow.
+    pub(crate) fn eval_int(&self, l: i64, r: i64) -> Result<Value, Error> {
+        let r_span = self.r_expr.span();
+        let n = match self.op {
+            Operator::Add => l.checked_add(r),
+            Operator::Sub => l.checked_sub(r),
+            Operator::Mul => l.checked_mul(r),
+            Operator::Div => None,
+            Operator::Pow => u32::try_from(r).ok().and_then(|r| l.checked_pow(r)),
+            Operator::Mod | Operator::FDiv if r == 0 => {
+                return Err(Error::new(ErrorKinds::ModuloByZero, vec![r_span.clone()]))
             }
-            Operator::Mul => {
-                Ok((l_number.0 * r_number.0, l_number.1))
+            Operator::Mod => l.checked_rem_euclid(r),
+            Operator::FDiv => l.checked_div(r),
+            Operator::BitAnd => Some(l & r),
+            Operator::BitOr => Some(l | r),
+            Operator::Xor => Some(l ^ r),
+            Operator::Shl | Operator::Shr => {
+                let shift = u32::try_from(r)
+                    .ok()
+                    .filter(|r| *r < i64::BITS)
+                    .ok_or_else(|| Error::new(ErrorKinds::NotInRange(0., 63.), vec![r_span.clone()]))?;
+                Some(if self.op == Operator::Shl { l << shift } else { l >> shift })
             }
+            Operator::Eq => return Ok(Value::Bool(l == r)),
+            Operator::Ne => return Ok(Value::Bool(l != r)),
+            Operator::Lt => return Ok(Value::Bool(l < r)),
+            Operator::Le => return Ok(Value::Bool(l <= r)),
+            Operator::Gt => return Ok(Value::Bool(l > r)),
+            Operator::Ge => return Ok(Value::Bool(l >= r)),
+            Operator::And | Operator::Or | Operator::PlusMinus => unreachable!(),
+        };
+        match n {
+            Some(n) => Ok(Value::Int(n)),
+            None => self.eval_float(l as f64, r as f64),
+        }
+    }
+
+    pub(crate) fn eval_float(&self, l: f64, r: f64) -> Result<Value, Error> {
+        let r_span = self.r_expr.span();
+        match self.op {
+            Operator::Add => Ok(Value::Float(l + r)),
+            Operator::Sub => Ok(Value::Float(l - r)),
+            Operator::Mul => Ok(Value::Float(l * r)),
             Operator::Div => {
-                if r_number.0 == 0. {
-                    return Err(Error::new(ErrorKinds::DivisionByZero, vec![r_number.1]));
+                if r == 0. {
+                    return Err(Error::new(ErrorKinds::DivisionByZero, vec![r_span.clone()]));
                 }
-                Ok((l_number.0 / r_number.0, l_number.1))
-            }
-            Operator::Pow => {
-                Ok((l_number.0.powf(r_number.0), l_number.1))
+                Ok(Value::Float(l / r))
             }
-            Operator::Mod => {
-                Ok((l_number.0.rem_euclid(r_number.0), l_number.1))
-            }
-            Operator::FDiv => {
-                if r_number.0 == 0. {
-                    return Err(Error::new(ErrorKinds::ModuloByZero, vec![r_number.1]));
-                }
-                Ok(((l_number.0 / r_number.0).trunc(), l_number.1))
+            Operator::Pow => Ok(Value::Float(l.powf(r))),
+            Operator::Mod | Operator::FDiv if r == 0. => {
+                Err(Error::new(ErrorKinds::ModuloByZero, vec![r_span.clone()]))
             }
+            Operator::Mod => Ok(Value::Float(l.rem_euclid(r))),
+            Operator::FDiv => Ok(Value::Float((l / r).trunc())),
+            Operator::Eq => Ok(Value::Bool(l == r)),
+            Operator::Ne => Ok(Value::Bool(l != r)),
+            Operator::Lt => Ok(Value::Bool(l < r)),
+            Operator::Le => Ok(Value::Bool(l <= r)),
+            Operator::Gt => Ok(Value::Bool(l > r)),
+            Operator::Ge => Ok(Value::Bool(l >= r)),
+            Operator::BitAnd
+            | Operator::BitOr
+            | Operator::Xor
+            | Operator::Shl
+            | Operator::Shr
+            | Operator::And
+            | Operator::Or
+            | Operator::PlusMinus => unreachable!(),
         }
     }
-}
\ No newline at end of file
+}
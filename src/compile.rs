@@ -0,0 +1,489 @@
+use crate::ast::consts::Const;
+use crate::ast::expr::Expr;
+use crate::ast::function::{self, CustomFunction, Function};
+use crate::ast::token::operator::{Operator, UnaryOp};
+use crate::ast::tree::TreeNode;
+use crate::context::Context;
+use crate::eval::Eval;
+use crate::maph_error::{Error, ErrorKinds, Span};
+use crate::numeric::{Numeric, Rounding};
+use crate::value::Value;
+
+/// An expression compiled to the bytecode of a stack machine, to evaluate it
+/// quickly for many values of its variables, see [`Expr::compile`].
+///
+/// Every variable is given a slot, in the order they first appear in the
+/// expression, and [`Program::eval`] takes their values in that order.
+#[derive(Debug, Clone)]
+pub struct Program {
+    code: Vec<Instr>,
+    // Span of the expression each instruction reports its errors on.
+    spans: Vec<Span>,
+    variables: Vec<(String, Span)>,
+    functions: Vec<CustomFunction>,
+    stack_size: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Instr {
+    Push(f64),
+    Load(usize),
+    Neg,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Mod,
+    FDiv,
+    /// A function without error.
+    Math(fn(f64) -> f64),
+    Sqrt,
+    ArcSin,
+    ArcCos,
+    ArcTan2,
+    LogBased,
+    Arg,
+    Round(Rounding),
+    /// `round(x, digits)`, with `digits` on the top of the stack.
+    RoundDigits(Rounding),
+    Max(usize),
+    Min(usize),
+    Custom(usize),
+    Pop,
+    // Comparisons push `1` or `0`, only in conditions.
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Not,
+    Jump(usize),
+    /// Pop the top of the stack and jump if it is `0`.
+    JumpIfZero(usize),
+}
+
+impl Program {
+    /// Names of the variables, in the order of their slots.
+    pub fn variables(&self) -> impl Iterator<Item = &str> + '_ {
+        self.variables.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Slot of the variable `name`.
+    pub fn slot(&self, name: &str) -> Option<usize> {
+        self.variables.iter().position(|(variable, _)| variable == name)
+    }
+
+    /// Evaluate the program with the value of every variable in `values`, by
+    /// slot. Booleans are `1` and `0`.
+    pub fn eval(&self, values: &[f64]) -> Result<f64, Error> {
+        if values.len() != self.variables.len() {
+            let kind = ErrorKinds::WrongArgumentCount { expected: self.variables.len(), found: values.len() };
+            return Err(Error::new(kind, vec![]));
+        }
+        let mut stack = Vec::with_capacity(self.stack_size);
+        let mut pc = 0;
+        while let Some(&instr) = self.code.get(pc) {
+            let span = &self.spans[pc];
+            let error = |kind: ErrorKinds| Error::new(kind, vec![span.clone()]);
+            pc += 1;
+            match instr {
+                Instr::Push(n) => stack.push(n),
+                Instr::Load(slot) => stack.push(values[slot]),
+                Instr::Neg => unary(&mut stack, |n| -n),
+                Instr::Add => binary(&mut stack, |l, r| l + r),
+                Instr::Sub => binary(&mut stack, |l, r| l - r),
+                Instr::Mul => binary(&mut stack, |l, r| l * r),
+                Instr::Div if stack.last() == Some(&0.) => return Err(error(ErrorKinds::DivisionByZero)),
+                Instr::Div => binary(&mut stack, |l, r| l / r),
+                Instr::Pow => binary(&mut stack, f64::powf),
+                Instr::Mod | Instr::FDiv if stack.last() == Some(&0.) => return Err(error(ErrorKinds::ModuloByZero)),
+                Instr::Mod => binary(&mut stack, f64::rem_euclid),
+                Instr::FDiv => binary(&mut stack, |l, r| (l / r).trunc()),
+                Instr::Math(f) => unary(&mut stack, f),
+                Instr::Sqrt if top(&stack) < 0. => return Err(error(ErrorKinds::NegativeSqrt)),
+                Instr::Sqrt => unary(&mut stack, f64::sqrt),
+                Instr::ArcSin | Instr::ArcCos if !(-1. ..=1.).contains(&top(&stack)) => {
+                    return Err(error(ErrorKinds::NotInRange(-1., 1.)));
+                }
+                Instr::ArcSin => unary(&mut stack, f64::asin),
+                Instr::ArcCos => unary(&mut stack, f64::acos),
+                Instr::ArcTan2 => binary(&mut stack, f64::atan2),
+                Instr::LogBased => binary(&mut stack, function::log_based),
+                Instr::Arg => unary(&mut stack, |n| 0f64.atan2(n)),
+                Instr::Round(rounding) => unary(&mut stack, |n| n.round_to(0, rounding).unwrap_or(f64::NAN)),
+                Instr::RoundDigits(rounding) => {
+                    let digits = Value::Float(top(&stack)).to_integer(span)?;
+                    binary(&mut stack, |n, _| n.round_to(digits, rounding).unwrap_or(f64::NAN));
+                }
+                Instr::Max(count) | Instr::Min(count) => {
+                    let args = stack.split_off(stack.len() - count);
+                    let fold: fn(f64, f64) -> f64 = if matches!(instr, Instr::Max(_)) { f64::max } else { f64::min };
+                    stack.push(args[1..].iter().copied().fold(args[0], fold));
+                }
+                Instr::Custom(index) => {
+                    let function = &self.functions[index];
+                    let start = stack.len() - function.arity;
+                    let result = function.call(&stack[start..]);
+                    stack.truncate(start);
+                    stack.push(result);
+                }
+                Instr::Pop => {
+                    stack.pop();
+                }
+                Instr::Eq => binary(&mut stack, |l, r| truth(l == r)),
+                Instr::Ne => binary(&mut stack, |l, r| truth(l != r)),
+                Instr::Lt => binary(&mut stack, |l, r| truth(l < r)),
+                Instr::Le => binary(&mut stack, |l, r| truth(l <= r)),
+                Instr::Gt => binary(&mut stack, |l, r| truth(l > r)),
+                Instr::Ge => binary(&mut stack, |l, r| truth(l >= r)),
+                Instr::Not => unary(&mut stack, |n| truth(n == 0.)),
+                Instr::Jump(target) => pc = target,
+                Instr::JumpIfZero(target) => {
+                    if stack.pop() == Some(0.) {
+                        pc = target;
+                    }
+                }
+            }
+        }
+        Ok(top(&stack))
+    }
+
+    /// Evaluate the program with the variables of `ctx`.
+    pub fn eval_with(&self, ctx: &Context) -> Result<f64, Error> {
+        let values = self
+            .variables
+            .iter()
+            .map(|(name, span)| match ctx.get_variable(name) {
+                Some(Value::Int(n)) => Ok(n as f64),
+                Some(Value::Float(n)) => Ok(n),
+                Some(Value::Bool(b)) => Ok(truth(b)),
+                None => Err(Error::new(ErrorKinds::UnknownVariable(name.clone()), vec![span.clone()])),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.eval(&values)
+    }
+}
+
+#[inline]
+fn top(stack: &[f64]) -> f64 {
+    stack.last().copied().unwrap_or(f64::NAN)
+}
+
+#[inline]
+fn unary(stack: &mut [f64], f: impl Fn(f64) -> f64) {
+    if let Some(n) = stack.last_mut() {
+        *n = f(*n);
+    }
+}
+
+#[inline]
+fn binary(stack: &mut Vec<f64>, f: impl Fn(f64, f64) -> f64) {
+    if let (Some(r), Some(l)) = (stack.pop(), stack.last_mut()) {
+        *l = f(*l, r);
+    }
+}
+
+#[inline]
+fn truth(b: bool) -> f64 {
+    if b { 1. } else { 0. }
+}
+
+// Compile `expr`, see `Expr::compile`.
+pub(crate) fn compile(expr: &Expr, ctx: &Context) -> Result<Program, Error> {
+    let mut compiler = Compiler {
+        ctx,
+        program: Program { code: vec![], spans: vec![], variables: vec![], functions: vec![], stack_size: 0 },
+        depth: 0,
+    };
+    compiler.expr(expr)?;
+    Ok(compiler.program)
+}
+
+struct Compiler<'a> {
+    ctx: &'a Context,
+    program: Program,
+    // Size of the stack after the instructions emitted so far.
+    depth: usize,
+}
+
+impl Compiler<'_> {
+    fn emit(&mut self, instr: Instr, span: &Span) -> usize {
+        let (popped, pushed) = match instr {
+            Instr::Push(_) | Instr::Load(_) => (0, 1),
+            Instr::Add
+            | Instr::Sub
+            | Instr::Mul
+            | Instr::Div
+            | Instr::Pow
+            | Instr::Mod
+            | Instr::FDiv
+            | Instr::ArcTan2
+            | Instr::LogBased
+            | Instr::RoundDigits(_)
+            | Instr::Eq
+            | Instr::Ne
+            | Instr::Lt
+            | Instr::Le
+            | Instr::Gt
+            | Instr::Ge => (2, 1),
+            Instr::Max(count) | Instr::Min(count) => (count, 1),
+            Instr::Custom(index) => (self.program.functions[index].arity, 1),
+            Instr::Pop | Instr::JumpIfZero(_) => (1, 0),
+            Instr::Jump(_) => (0, 0),
+            _ => (1, 1),
+        };
+        self.depth = self.depth + pushed - popped;
+        self.program.stack_size = self.program.stack_size.max(self.depth);
+        self.program.code.push(instr);
+        self.program.spans.push(span.clone());
+        self.program.code.len() - 1
+    }
+
+    // Point the jump at `index` to the next instruction.
+    fn patch(&mut self, index: usize) {
+        let target = self.program.code.len();
+        match &mut self.program.code[index] {
+            Instr::Jump(to) | Instr::JumpIfZero(to) => *to = target,
+            _ => unreachable!(),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<(), Error> {
+        match expr {
+            Expr::Node(node) => self.node(node)?,
+            Expr::Number(n, _, span) => {
+                self.emit(Instr::Push(*n), span);
+            }
+            Expr::Int(n, span) => {
+                self.emit(Instr::Push(*n as f64), span);
+            }
+            Expr::Const(Const::Custom(_, value, span)) => {
+                self.emit(Instr::Push(*value), span);
+            }
+            Expr::Const(c) => {
+                let value = c.eval_with(self.ctx)?.to_number(c.span())?;
+                self.emit(Instr::Push(value), c.span());
+            }
+            Expr::Variable(name, span) => {
+                let slot = self.program.slot(name).unwrap_or_else(|| {
+                    self.program.variables.push((name.clone(), span.clone()));
+                    self.program.variables.len() - 1
+                });
+                self.emit(Instr::Load(slot), span);
+            }
+            Expr::Function(fnc) => self.function(fnc)?,
+            Expr::Unary(UnaryOp::Neg, expr, span) => {
+                self.expr(expr)?;
+                self.emit(Instr::Neg, span);
+            }
+            Expr::Unary(UnaryOp::Plus, expr, _) => self.expr(expr)?,
+            Expr::Unary(UnaryOp::Not, _, span) => return Err(unsupported(UnaryOp::Not, span)),
+            Expr::Interval(_, _, span) => return Err(unsupported("[lo, hi]", span)),
+        }
+        Ok(())
+    }
+
+    fn node(&mut self, node: &TreeNode) -> Result<(), Error> {
+        let instr = match node.op {
+            Operator::Add => Instr::Add,
+            Operator::Sub => Instr::Sub,
+            Operator::Mul => Instr::Mul,
+            Operator::Div => Instr::Div,
+            Operator::Pow => Instr::Pow,
+            Operator::Mod => Instr::Mod,
+            Operator::FDiv => Instr::FDiv,
+            op => return Err(unsupported(op, &node.span)),
+        };
+        self.expr(&node.l_expr)?;
+        self.expr(&node.r_expr)?;
+        // A division by zero points to the divisor.
+        let span = if matches!(instr, Instr::Div | Instr::Mod | Instr::FDiv) { node.r_expr.span() } else { &node.span };
+        self.emit(instr, span);
+        Ok(())
+    }
+
+    fn function(&mut self, fnc: &Function) -> Result<(), Error> {
+        let span = fnc.span();
+        let instr = match fnc {
+            Function::Sqrt(..) => Instr::Sqrt,
+            Function::Cbrq(..) => Instr::Math(f64::cbrt),
+            Function::Abs(..) => Instr::Math(f64::abs),
+            Function::Sin(..) => Instr::Math(f64::sin),
+            Function::Cos(..) => Instr::Math(f64::cos),
+            Function::Tan(..) => Instr::Math(f64::tan),
+            Function::ArcSin(..) => Instr::ArcSin,
+            Function::ArcCos(..) => Instr::ArcCos,
+            Function::ArcTan(..) => Instr::Math(f64::atan),
+            Function::ArcTan2(..) => Instr::ArcTan2,
+            Function::Ln(..) => Instr::Math(f64::ln),
+            Function::Log(..) => Instr::Math(f64::log10),
+            Function::LogBased(..) => Instr::LogBased,
+            Function::Round(_, None, _) => Instr::Round(self.ctx.rounding()),
+            Function::Round(_, Some(_), _) => Instr::RoundDigits(self.ctx.rounding()),
+            Function::Floor(..) => Instr::Math(f64::floor),
+            Function::Ceil(..) => Instr::Math(f64::ceil),
+            Function::Trunc(..) => Instr::Math(f64::trunc),
+            Function::Frac(..) => Instr::Math(f64::fract),
+            Function::Deg(..) => Instr::Math(f64::to_degrees),
+            Function::Rad(..) => Instr::Math(f64::to_radians),
+            Function::Max(args, _) => Instr::Max(args.len()),
+            Function::Min(args, _) => Instr::Min(args.len()),
+            Function::Re(expr, _) | Function::Conj(expr, _) => return self.expr(expr),
+            Function::Im(expr, _) => {
+                self.expr(expr)?;
+                self.emit(Instr::Pop, span);
+                Instr::Push(0.)
+            }
+            Function::Arg(..) => Instr::Arg,
+            Function::If(condition, then, otherwise, _) => {
+                self.condition(condition)?;
+                let to_otherwise = self.emit(Instr::JumpIfZero(0), span);
+                self.expr(then)?;
+                let to_end = self.emit(Instr::Jump(0), span);
+                self.patch(to_otherwise);
+                // Only one of the branches pushes its result.
+                self.depth -= 1;
+                self.expr(otherwise)?;
+                self.patch(to_end);
+                return Ok(());
+            }
+            Function::Custom(custom, ..) => {
+                self.program.functions.push(custom.clone());
+                Instr::Custom(self.program.functions.len() - 1)
+            }
+        };
+        if !matches!(fnc, Function::Im(..)) {
+            for arg in fnc.args() {
+                self.expr(arg)?;
+            }
+        }
+        // `round` reports a wrong number of digits on them.
+        let span = match fnc {
+            Function::Round(_, Some(digits), _) => digits.span(),
+            _ => span,
+        };
+        self.emit(instr, span);
+        Ok(())
+    }
+
+    // Compile the condition of an `if`, which is false when it pushes `0`.
+    fn condition(&mut self, expr: &Expr) -> Result<(), Error> {
+        match expr {
+            Expr::Node(node) if matches!(node.op, Operator::And | Operator::Or) => {
+                let span = &node.span;
+                self.condition(&node.l_expr)?;
+                let to_r_expr = self.emit(Instr::JumpIfZero(0), span);
+                // `&&` is false, and `||` true, without evaluating its right operand.
+                if node.op == Operator::And {
+                    self.condition(&node.r_expr)?;
+                    let to_end = self.emit(Instr::Jump(0), span);
+                    self.patch(to_r_expr);
+                    self.depth -= 1;
+                    self.emit(Instr::Push(0.), span);
+                    self.patch(to_end);
+                } else {
+                    self.emit(Instr::Push(1.), span);
+                    let to_end = self.emit(Instr::Jump(0), span);
+                    self.patch(to_r_expr);
+                    self.depth -= 1;
+                    self.condition(&node.r_expr)?;
+                    self.patch(to_end);
+                }
+            }
+            Expr::Node(node) if node.op.is_boolean() => {
+                let instr = match node.op {
+                    Operator::Eq => Instr::Eq,
+                    Operator::Ne => Instr::Ne,
+                    Operator::Lt => Instr::Lt,
+                    Operator::Le => Instr::Le,
+                    Operator::Gt => Instr::Gt,
+                    _ => Instr::Ge,
+                };
+                self.expr(&node.l_expr)?;
+                self.expr(&node.r_expr)?;
+                self.emit(instr, &node.span);
+            }
+            Expr::Unary(UnaryOp::Not, expr, span) => {
+                self.condition(expr)?;
+                self.emit(Instr::Not, span);
+            }
+            expr => self.expr(expr)?,
+        }
+        Ok(())
+    }
+}
+
+fn unsupported(op: impl ToString, span: &Span) -> Error {
+    Error::new(ErrorKinds::Unsupported(op.to_string()), vec![span.clone()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile_str(input: &str, ctx: &Context) -> Result<Program, Error> {
+        Expr::parse_with(input, ctx).unwrap().compile(ctx)
+    }
+
+    #[test]
+    fn compile_same_as_eval() {
+        let mut ctx = Context::new();
+        ctx.set_function("lerp", 3, |args| args[0] + (args[1] - args[0]) * args[2]);
+        for input in [
+            "3x^2 + 2x*y - sin(x) / (1 + y^2)",
+            "if(x > y || !(y != 0) && x < 0, sqrt(abs(x)), ln(1 + y^2)) + max(x, y, 0.5) - min(x, -y)",
+            "7 // (y + 3) + x % 3 + logb(8, 2) + round(x * 100, -1) + round(y, 1) + atan2(y, x)",
+            "lerp(x, y, 0.25) + re(x) + im(y) + arg(x) + floor(x) + cbrq(y) + pi * e + -+x",
+            "max(x, y) * 2 + min(y, x, 1) + arg(x) - arg(y) + max(x)",
+        ] {
+            let expr = Expr::parse_with(input, &ctx).unwrap();
+            let program = expr.compile(&ctx).unwrap();
+            for (x, y) in [(0., 0.), (1.5, -2.), (-3.25, 4.), (10., 0.1), (f64::NAN, 1.), (-0., f64::NAN), (-0., 0.)] {
+                ctx.set_variable("x", x);
+                ctx.set_variable("y", y);
+                let bits = |n: f64| if n.is_nan() { f64::NAN.to_bits() } else { n.to_bits() };
+                let result = program.eval_with(&ctx).map(bits);
+                assert_eq!(result, expr.eval_numeric::<f64>(&ctx).map(bits), "{} at ({}, {})", input, x, y);
+                let value = expr.eval_with(&ctx).map(|value| value.as_f64().unwrap());
+                assert_eq!(result, value.map(bits), "{} at ({}, {})", input, x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn compile_variable_slots() {
+        let ctx = Context::new();
+        let program = compile_str("b * a + if(a > 0, c, 1 / b)", &ctx).unwrap();
+        assert_eq!(program.variables().collect::<Vec<_>>(), ["b", "a", "c"]);
+        assert_eq!(program.slot("c"), Some(2));
+        assert_eq!(program.eval(&[2., 3., 4.]), Ok(10.));
+        // Only the chosen branch is evaluated.
+        assert_eq!(program.eval(&[0., -1., 4.]), Err(Error::new(ErrorKinds::DivisionByZero, vec![25..26])));
+        assert_eq!(program.eval(&[0., 1., 4.]), Ok(4.));
+        assert_eq!(
+            program.eval(&[1.]),
+            Err(Error::new(ErrorKinds::WrongArgumentCount { expected: 3, found: 1 }, vec![]))
+        );
+        assert_eq!(
+            program.eval_with(&ctx),
+            Err(Error::new(ErrorKinds::UnknownVariable("b".to_owned()), vec![0..1]))
+        );
+    }
+
+    #[test]
+    fn compile_errors() {
+        let ctx = Context::new();
+        assert_eq!(
+            compile_str("x & 1", &ctx).map(|_| ()),
+            Err(Error::new(ErrorKinds::Unsupported("&".to_owned()), vec![0..5]))
+        );
+        let program = compile_str("5.5 % x", &ctx).unwrap();
+        assert_eq!(program.eval(&[0.]), Err(Error::new(ErrorKinds::ModuloByZero, vec![6..7])));
+        let program = compile_str("sqrt(x) + asin(x / 2) + round(1, x)", &ctx).unwrap();
+        assert_eq!(program.eval(&[-1.]), Err(Error::new(ErrorKinds::NegativeSqrt, vec![0..7])));
+        assert_eq!(program.eval(&[4.]), Err(Error::new(ErrorKinds::NotInRange(-1., 1.), vec![10..21])));
+        assert_eq!(program.eval(&[0.5]), Err(Error::new(ErrorKinds::NotAnInteger(0.5), vec![33..34])));
+    }
+}
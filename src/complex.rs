@@ -0,0 +1,451 @@
+use std::f64::consts::{FRAC_PI_2, LN_10};
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::ast::consts::Const;
+use crate::ast::expr::Expr;
+use crate::ast::function::{self, Function};
+use crate::ast::token::operator::{Operator, UnaryOp};
+use crate::ast::tree::TreeNode;
+use crate::context::Context;
+use crate::eval::Eval;
+use crate::maph_error::{Error, ErrorKinds, Span};
+use crate::numeric::Numeric;
+use crate::value::Value;
+
+/// A complex number `re + im·i`, see [`Expr::eval_complex`].
+///
+/// The functions keep the real result for a real argument in their real
+/// domain, `cbrq(-8)` being `-2`, and take the principal value elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+const I: Complex = Complex { re: 0., im: 1. };
+
+impl Complex {
+    #[inline]
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    #[inline]
+    pub fn is_real(&self) -> bool {
+        self.im == 0.
+    }
+
+    /// The modulus `|z|`.
+    pub fn abs(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    /// The argument, in `(-π, π]`.
+    pub fn arg(&self) -> f64 {
+        // `-1` is `-(1 + 0i)`, whose argument would be `-π` with a signed zero.
+        (self.im + 0.).atan2(self.re)
+    }
+
+    pub fn conj(&self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn exp(self) -> Self {
+        if self.is_real() {
+            return self.re.exp().into();
+        }
+        let (sin, cos) = self.im.sin_cos();
+        Self::new(cos, sin) * self.re.exp().into()
+    }
+
+    pub fn ln(self) -> Self {
+        if self.is_real() && self.re > 0. {
+            return self.re.ln().into();
+        }
+        Self::new(self.abs().ln(), self.arg())
+    }
+
+    pub fn sqrt(self) -> Self {
+        if self.is_real() && self.re >= 0. {
+            return self.re.sqrt().into();
+        }
+        let abs = self.abs();
+        let im = ((abs - self.re) / 2.).sqrt();
+        Self::new(((abs + self.re) / 2.).sqrt(), if self.im < 0. { -im } else { im })
+    }
+
+    pub fn cbrt(self) -> Self {
+        if self.is_real() {
+            return self.re.cbrt().into();
+        }
+        (self.ln() / 3f64.into()).exp()
+    }
+
+    pub fn pow(self, exp: Self) -> Self {
+        if self.is_real() && exp.is_real() && (self.re >= 0. || exp.re.fract() == 0.) {
+            return self.re.powf(exp.re).into();
+        }
+        if exp.is_real() && exp.re.fract() == 0. && exp.re.abs() <= i32::MAX as f64 {
+            return self.powi(exp.re as i32);
+        }
+        if self == Self::default() {
+            return if exp.re > 0. { self } else { f64::NAN.into() };
+        }
+        (exp * self.ln()).exp()
+    }
+
+    // Exponentiation by squaring, so `i^2` is exactly `-1`.
+    fn powi(self, exp: i32) -> Self {
+        let (mut base, mut n, mut pow) = (self, exp.unsigned_abs(), Self::from(1.));
+        while n > 0 {
+            if n & 1 == 1 {
+                pow = pow * base;
+            }
+            base = base * base;
+            n >>= 1;
+        }
+        if exp < 0 { Self::from(1.) / pow } else { pow }
+    }
+
+    pub fn sin(self) -> Self {
+        if self.is_real() {
+            return self.re.sin().into();
+        }
+        Self::new(self.re.sin() * self.im.cosh(), self.re.cos() * self.im.sinh())
+    }
+
+    pub fn cos(self) -> Self {
+        if self.is_real() {
+            return self.re.cos().into();
+        }
+        Self::new(self.re.cos() * self.im.cosh(), -self.re.sin() * self.im.sinh())
+    }
+
+    pub fn tan(self) -> Self {
+        if self.is_real() {
+            return self.re.tan().into();
+        }
+        self.sin() / self.cos()
+    }
+
+    pub fn asin(self) -> Self {
+        if self.is_real() && (-1. ..=1.).contains(&self.re) {
+            return self.re.asin().into();
+        }
+        -I * (I * self + (Self::from(1.) - self * self).sqrt()).ln()
+    }
+
+    pub fn acos(self) -> Self {
+        if self.is_real() && (-1. ..=1.).contains(&self.re) {
+            return self.re.acos().into();
+        }
+        Self::from(FRAC_PI_2) - self.asin()
+    }
+
+    pub fn atan(self) -> Self {
+        if self.is_real() {
+            return self.re.atan().into();
+        }
+        I / 2f64.into() * ((Self::from(1.) - I * self).ln() - (Self::from(1.) + I * self).ln())
+    }
+
+    /// The argument of `x + y·i`, for complex `x` and `y` too.
+    pub fn atan2(self, x: Self) -> Self {
+        if self.is_real() && x.is_real() {
+            return self.re.atan2(x.re).into();
+        }
+        let z = x + I * self;
+        -I * (z / (x * x + self * self).sqrt()).ln()
+    }
+
+    fn map(self, f: impl Fn(f64) -> f64) -> Self {
+        Self::new(f(self.re), f(self.im))
+    }
+}
+
+impl From<f64> for Complex {
+    fn from(re: f64) -> Self {
+        Self::new(re, 0.)
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        // `inf * 2` would be `NaN` through the imaginary parts.
+        if self.is_real() && rhs.is_real() {
+            return (self.re * rhs.re).into();
+        }
+        Self::new(self.re * rhs.re - self.im * rhs.im, self.re * rhs.im + self.im * rhs.re)
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        if self.is_real() && rhs.is_real() {
+            return (self.re / rhs.re).into();
+        }
+        let norm = rhs.re * rhs.re + rhs.im * rhs.im;
+        Self::new(
+            (self.re * rhs.re + self.im * rhs.im) / norm,
+            (self.im * rhs.re - self.re * rhs.im) / norm,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.im)
+    }
+}
+
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_real() {
+            return write!(f, "{}", self.re);
+        }
+        if self.re != 0. {
+            write!(f, "{}{}", self.re, if self.im.is_sign_negative() { '-' } else { '+' })?;
+        } else if self.im.is_sign_negative() {
+            f.write_str("-")?;
+        }
+        if self.im.abs() == 1. {
+            f.write_str("i")
+        } else {
+            write!(f, "{}i", self.im.abs())
+        }
+    }
+}
+
+// Evaluator over complex numbers. Spans are only used for errors.
+pub(crate) fn eval(expr: &Expr, ctx: &Context) -> Result<Complex, Error> {
+    match expr {
+        Expr::Node(node) => eval_node(node, ctx),
+        Expr::Number(n, ..) => Ok((*n).into()),
+        Expr::Int(n, _) => Ok((*n as f64).into()),
+        Expr::Const(Const::I(_)) => Ok(I),
+        Expr::Const(c) => Ok(real(c.eval_with(ctx)?, c.span())?.into()),
+        Expr::Function(fnc) => eval_function(fnc, ctx),
+        Expr::Variable(..) => Ok(real(expr.eval_with(ctx)?, expr.span())?.into()),
+        Expr::Unary(UnaryOp::Neg, expr, _) => Ok(-eval(expr, ctx)?),
+        Expr::Unary(UnaryOp::Plus, expr, _) => eval(expr, ctx),
+        Expr::Unary(UnaryOp::Not, _, span) => Err(unsupported(UnaryOp::Not, span)),
+        Expr::Interval(_, _, span) => Err(unsupported("[lo, hi]", span)),
+    }
+}
+
+fn eval_node(node: &TreeNode, ctx: &Context) -> Result<Complex, Error> {
+    let l = eval(&node.l_expr, ctx)?;
+    let r = eval(&node.r_expr, ctx)?;
+    let (l_span, r_span) = (node.l_expr.span(), node.r_expr.span());
+    match node.op {
+        Operator::Add => Ok(l + r),
+        Operator::Sub => Ok(l - r),
+        Operator::Mul => Ok(l * r),
+        Operator::Div if r == Complex::default() => {
+            Err(Error::new(ErrorKinds::DivisionByZero, vec![r_span.clone()]))
+        }
+        Operator::Div => Ok(l / r),
+        Operator::Pow => Ok(l.pow(r)),
+        // Other operators are the real ones, on real operands.
+        op if op.is_boolean() || op == Operator::PlusMinus || !l.is_real() || !r.is_real() => {
+            Err(unsupported(op, &node.span))
+        }
+        op if op.is_bitwise() => {
+            let (l, r) = (Value::Float(l.re).to_integer(l_span)?, Value::Float(r.re).to_integer(r_span)?);
+            Ok(real(node.eval_int(l, r)?, &node.span)?.into())
+        }
+        _ => Ok(real(node.eval_float(l.re, r.re)?, &node.span)?.into()),
+    }
+}
+
+fn eval_function(fnc: &Function, ctx: &Context) -> Result<Complex, Error> {
+    let arg = |expr: &Expr| eval(expr, ctx);
+    match fnc {
+        Function::Sqrt(expr, _) => Ok(arg(expr)?.sqrt()),
+        Function::Cbrq(expr, _) => Ok(arg(expr)?.cbrt()),
+        Function::Abs(expr, _) => Ok(arg(expr)?.abs().into()),
+        Function::Sin(expr, _) => Ok(arg(expr)?.sin()),
+        Function::Cos(expr, _) => Ok(arg(expr)?.cos()),
+        Function::Tan(expr, _) => Ok(arg(expr)?.tan()),
+        Function::ArcSin(expr, _) => Ok(arg(expr)?.asin()),
+        Function::ArcCos(expr, _) => Ok(arg(expr)?.acos()),
+        Function::ArcTan(expr, _) => Ok(arg(expr)?.atan()),
+        Function::ArcTan2(y, x, _) => Ok(arg(y)?.atan2(arg(x)?)),
+        Function::Ln(expr, _) => Ok(arg(expr)?.ln()),
+        Function::Log(expr, _) => {
+            let z = arg(expr)?;
+            if z.is_real() && z.re > 0. {
+                return Ok(z.re.log10().into());
+            }
+            Ok(z.ln() / LN_10.into())
+        }
+        Function::LogBased(expr, base, _) => {
+            let (z, base) = (arg(expr)?, arg(base)?);
+            if z.is_real() && z.re > 0. && base.is_real() && base.re > 0. {
+                return Ok(function::log_based(z.re, base.re).into());
+            }
+            Ok(z.ln() / base.ln())
+        }
+        Function::Round(expr, digits, _) => {
+            let digits = match digits {
+                Some(digits) => Value::Float(real_arg(digits, ctx)?).to_integer(digits.span())?,
+                None => 0,
+            };
+            Ok(arg(expr)?.map(|n| n.round_to(digits, ctx.rounding()).unwrap_or(f64::NAN)))
+        }
+        Function::Floor(expr, _) => Ok(arg(expr)?.map(f64::floor)),
+        Function::Ceil(expr, _) => Ok(arg(expr)?.map(f64::ceil)),
+        Function::Trunc(expr, _) => Ok(arg(expr)?.map(f64::trunc)),
+        Function::Frac(expr, _) => Ok(arg(expr)?.map(f64::fract)),
+        Function::Deg(expr, _) => Ok(arg(expr)?.map(f64::to_degrees)),
+        Function::Rad(expr, _) => Ok(arg(expr)?.map(f64::to_radians)),
+        Function::Re(expr, _) => Ok(arg(expr)?.re.into()),
+        Function::Im(expr, _) => Ok(arg(expr)?.im.into()),
+        Function::Arg(expr, _) => Ok(arg(expr)?.arg().into()),
+        Function::Conj(expr, _) => Ok(arg(expr)?.conj()),
+        Function::If(condition, then, otherwise, _) => {
+            let branch = if eval_condition(condition, ctx)? { then } else { otherwise };
+            eval(branch, ctx)
+        }
+        // Functions which are only defined on real numbers.
+        Function::Max(args, _) => {
+            let first = real_arg(&args[0], ctx)?;
+            args[1..].iter().try_fold(first, |max, arg| Ok(max.max(real_arg(arg, ctx)?))).map(Complex::from)
+        }
+        Function::Min(args, _) => {
+            let first = real_arg(&args[0], ctx)?;
+            args[1..].iter().try_fold(first, |min, arg| Ok(min.min(real_arg(arg, ctx)?))).map(Complex::from)
+        }
+        Function::Custom(function, args, _) => {
+            let args = args.iter().map(|arg| real_arg(arg, ctx)).collect::<Result<Vec<f64>, Error>>()?;
+            Ok(function.call(&args).into())
+        }
+    }
+}
+
+// Evaluate the condition of an `if`, where only `==` and `!=` accept complex operands.
+fn eval_condition(expr: &Expr, ctx: &Context) -> Result<bool, Error> {
+    match expr {
+        Expr::Node(node) if node.op == Operator::And => {
+            Ok(eval_condition(&node.l_expr, ctx)? && eval_condition(&node.r_expr, ctx)?)
+        }
+        Expr::Node(node) if node.op == Operator::Or => {
+            Ok(eval_condition(&node.l_expr, ctx)? || eval_condition(&node.r_expr, ctx)?)
+        }
+        Expr::Node(node) if node.op.is_boolean() => {
+            let l = eval(&node.l_expr, ctx)?;
+            let r = eval(&node.r_expr, ctx)?;
+            match node.op {
+                Operator::Eq => Ok(l == r),
+                Operator::Ne => Ok(l != r),
+                op if !l.is_real() || !r.is_real() => Err(unsupported(op, &node.span)),
+                Operator::Lt => Ok(l.re < r.re),
+                Operator::Le => Ok(l.re <= r.re),
+                Operator::Gt => Ok(l.re > r.re),
+                _ => Ok(l.re >= r.re),
+            }
+        }
+        Expr::Unary(UnaryOp::Not, expr, _) => Ok(!eval_condition(expr, ctx)?),
+        Expr::Variable(name, _) => match ctx.get_variable(name) {
+            Some(Value::Bool(b)) => Ok(b),
+            _ => Ok(eval(expr, ctx)? != Complex::default()),
+        },
+        expr => Ok(eval(expr, ctx)? != Complex::default()),
+    }
+}
+
+// A real result of the `Value` evaluation.
+fn real(value: Value, span: &Span) -> Result<f64, Error> {
+    value.to_number(span)
+}
+
+// Evaluate an argument which must be a real number.
+fn real_arg(expr: &Expr, ctx: &Context) -> Result<f64, Error> {
+    let z = eval(expr, ctx)?;
+    if !z.is_real() {
+        return Err(Error::new(ErrorKinds::NotReal, vec![expr.span().clone()]));
+    }
+    Ok(z.re)
+}
+
+fn unsupported(op: impl ToString, span: &Span) -> Error {
+    Error::new(ErrorKinds::Unsupported(op.to_string()), vec![span.clone()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval_str(input: &str) -> Result<Complex, Error> {
+        let mut ctx = Context::new();
+        ctx.set_complex(true);
+        Expr::parse_with(input, &ctx).unwrap().eval_complex(&ctx)
+    }
+
+    #[test]
+    fn complex_arithmetic() {
+        assert_eq!(eval_str("sqrt(-4)"), Ok(Complex::new(0., 2.)));
+        assert_eq!(eval_str("(1 + 2i) * (3 - j)"), Ok(Complex::new(5., 5.)));
+        assert_eq!(eval_str("(5 + 5i) / (3 - i)"), Ok(Complex::new(1., 2.)));
+        assert_eq!(eval_str("i^2 + i^-1"), Ok(Complex::new(-1., -1.)));
+        assert_eq!(eval_str("2^3 + 2 xor 1 + 7 % 4"), Ok(Complex::from(14.)));
+        assert_eq!(eval_str("1 / (i - i)"), Err(Error::new(ErrorKinds::DivisionByZero, vec![5..10])));
+        assert_eq!(eval_str("i % 2"), Err(Error::new(ErrorKinds::Unsupported("%".to_owned()), vec![0..5])));
+    }
+
+    #[test]
+    fn complex_functions() {
+        let ln = eval_str("ln(-1)").unwrap();
+        assert_eq!(ln, Complex::new(0., std::f64::consts::PI));
+        assert_eq!(eval_str("re(3 - 4i) + im(3 - 4i) + abs(3 - 4i)"), Ok(Complex::from(4.)));
+        assert_eq!(eval_str("conj(3 - 4i) + arg(-1)"), Ok(Complex::new(3. + std::f64::consts::PI, 4.)));
+        assert_eq!(eval_str("cbrq(-8) + floor(2.5 - 1.5i)"), Ok(Complex::new(0., -2.)));
+        let z = eval_str("sin(asin(2 + i))").unwrap();
+        assert!((z.re - 2.).abs() < 1e-12 && (z.im - 1.).abs() < 1e-12);
+        let z = eval_str("e^(pi i) + 1").unwrap();
+        assert!(z.abs() < 1e-15);
+        assert_eq!(eval_str("if(i^2 == -1, 1, 0)"), Ok(Complex::from(1.)));
+        assert_eq!(eval_str("max(1, i)"), Err(Error::new(ErrorKinds::NotReal, vec![7..8])));
+        assert_eq!(eval_str("max(i^2, -2) + min(sqrt(-4) * i, 0)"), Ok(Complex::from(-3.)));
+        assert_eq!(eval_str("logb(i^4 * 8, 2) + round(2.25, (2i)^2 / -4)"), Ok(Complex::from(5.3)));
+        let mut ctx = Context::new();
+        ctx.set_complex(true);
+        ctx.set_function("sum", 2, |args| args[0] + args[1]);
+        let expr = Expr::parse_with("sum(i * i, 3) + sum(i, 1)", &ctx).unwrap();
+        assert_eq!(expr.eval_complex(&ctx), Err(Error::new(ErrorKinds::NotReal, vec![20..21])));
+        let expr = Expr::parse_with("sum(i * i, 3)", &ctx).unwrap();
+        assert_eq!(expr.eval_complex(&ctx), Ok(Complex::from(2.)));
+    }
+
+    #[test]
+    fn display_complex() {
+        assert_eq!(Complex::new(3., -2.).to_string(), "3-2i");
+        assert_eq!(Complex::new(1.5, 0.5).to_string(), "1.5+0.5i");
+        assert_eq!(Complex::new(0., 2.).to_string(), "2i");
+        assert_eq!(Complex::new(0., -1.).to_string(), "-i");
+        assert_eq!(Complex::new(-4., 0.).to_string(), "-4");
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use crate::ast::function::CustomFunction;
+use crate::ast::token::NumberLocale;
+use crate::numeric::{Irrational, Rounding};
+use crate::value::Value;
+
+/// Values bound to the variables of an expression during its evaluation,
+/// and the constants, functions and number format used when parsing.
+#[derive(Debug, Clone)]
+pub struct Context {
+    variables: HashMap<String, Value>,
+    constants: HashMap<String, f64>,
+    functions: HashMap<String, CustomFunction>,
+    locale: NumberLocale,
+    implicit_multiplication: bool,
+    irrational: Irrational,
+    precision: u64,
+    rounding: Rounding,
+    complex: bool,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self {
+            variables: HashMap::new(),
+            constants: HashMap::new(),
+            functions: HashMap::new(),
+            locale: NumberLocale::default(),
+            implicit_multiplication: true,
+            irrational: Irrational::default(),
+            precision: 28,
+            rounding: Rounding::default(),
+            complex: false,
+        }
+    }
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bind `name` to `value`, returning the previous value if any.
+    pub fn set_variable(&mut self, name: impl Into<String>, value: impl Into<Value>) -> Option<Value> {
+        self.variables.insert(name.into(), value.into())
+    }
+
+    #[inline]
+    pub fn get_variable(&self, name: &str) -> Option<Value> {
+        self.variables.get(name).copied()
+    }
+
+    pub fn remove_variable(&mut self, name: &str) -> Option<Value> {
+        self.variables.remove(name)
+    }
+
+    /// Register a constant called `name`, returning the previous value if any.
+    /// It is resolved when parsing and takes precedence over a builtin constant
+    /// and over a variable with the same name.
+    pub fn set_constant(&mut self, name: impl Into<String>, value: f64) -> Option<f64> {
+        self.constants.insert(name.into(), value)
+    }
+
+    #[inline]
+    pub fn get_constant(&self, name: &str) -> Option<f64> {
+        self.constants.get(name).copied()
+    }
+
+    pub fn remove_constant(&mut self, name: &str) -> Option<f64> {
+        self.constants.remove(name)
+    }
+
+    /// Register a function called `name` taking `arity` arguments.
+    /// It takes precedence over a builtin function with the same name.
+    pub fn set_function(
+        &mut self,
+        name: impl Into<String>,
+        arity: usize,
+        function: impl Fn(&[f64]) -> f64 + Send + Sync + 'static,
+    ) -> Option<CustomFunction> {
+        let name = name.into();
+        self.functions.insert(name.clone(), CustomFunction::new(name, arity, function))
+    }
+
+    #[inline]
+    pub fn get_function(&self, name: &str) -> Option<&CustomFunction> {
+        self.functions.get(name)
+    }
+
+    pub fn remove_function(&mut self, name: &str) -> Option<CustomFunction> {
+        self.functions.remove(name)
+    }
+
+    pub fn set_locale(&mut self, locale: NumberLocale) {
+        self.locale = locale
+    }
+
+    #[inline]
+    pub fn locale(&self) -> &NumberLocale {
+        &self.locale
+    }
+
+    /// Enable or disable implicit multiplication, enabled by default.
+    ///
+    /// When enabled, an operand directly followed by a bracket, a name or `√`
+    /// is multiplied by it: `2x`, `3(x+1)`, `(a)(b)`, `2pi r`.
+    /// It binds tighter than `*`, `/`, `%` and `//` but looser than `^`,
+    /// so `1/2(3)` is `1/(2*3)` and `2x^2` is `2*(x^2)`.
+    /// When disabled (strict mode), such an expression is a `MissingOperator` error.
+    pub fn set_implicit_multiplication(&mut self, enabled: bool) {
+        self.implicit_multiplication = enabled
+    }
+
+    #[inline]
+    pub fn implicit_multiplication(&self) -> bool {
+        self.implicit_multiplication
+    }
+
+    /// Whether an irrational result, such as `sin(1)`, is rejected or
+    /// approximated when evaluating to an exact number type. Rejected by default.
+    pub fn set_irrational(&mut self, irrational: Irrational) {
+        self.irrational = irrational
+    }
+
+    #[inline]
+    pub fn irrational(&self) -> Irrational {
+        self.irrational
+    }
+
+    /// Set the number of significant digits kept by a decimal result, 28 by default.
+    /// A precision of `0` is treated as `1`.
+    pub fn set_precision(&mut self, precision: u64) {
+        self.precision = precision
+    }
+
+    #[inline]
+    pub fn precision(&self) -> u64 {
+        self.precision
+    }
+
+    /// Set how `round` and the decimal results are rounded,
+    /// [`Rounding::HalfUp`] by default.
+    pub fn set_rounding(&mut self, rounding: Rounding) {
+        self.rounding = rounding
+    }
+
+    #[inline]
+    pub fn rounding(&self) -> Rounding {
+        self.rounding
+    }
+
+    /// Enable or disable complex mode, disabled by default.
+    ///
+    /// When enabled, `i` and `j` are parsed as the imaginary unit, unless a
+    /// constant has the same name, so `3 + 2i` is a complex number evaluated
+    /// with [`Expr::eval_complex`](crate::Expr::eval_complex).
+    pub fn set_complex(&mut self, enabled: bool) {
+        self.complex = enabled
+    }
+
+    #[inline]
+    pub fn complex(&self) -> bool {
+        self.complex
+    }
+}
@@ -0,0 +1,317 @@
+use crate::ast::consts::Const;
+use crate::ast::expr::Expr;
+use crate::ast::function::Function;
+use crate::ast::token::operator::{Operator, UnaryOp};
+use crate::ast::tree::TreeNode;
+use crate::maph_error::{Error, ErrorKinds, Span};
+
+/// Derivative of `expr` with respect to the variable `var`, every other
+/// variable being a constant.
+///
+/// The nodes of the derivative take the span of the subexpression they are
+/// derived from. Operands equal to `0` or `1` are left out, so the
+/// derivative of an expression without `var` is `0`. Piecewise constant
+/// functions such as `floor` or `round`, and the other operators than `+`,
+/// `-`, `*`, `/` and `^`, have no derivative unless their operands are
+/// constant.
+pub fn derive(expr: &Expr, var: &str) -> Result<Expr, Error> {
+    let derivative = match expr {
+        Expr::Node(node) => derive_node(node, var),
+        Expr::Variable(name, span) if name == var => Ok(Expr::Int(1, span.clone())),
+        Expr::Number(_, _, span) | Expr::Int(_, span) | Expr::Variable(_, span) => Ok(Expr::Int(0, span.clone())),
+        Expr::Const(c) => Ok(Expr::Int(0, c.span().clone())),
+        Expr::Function(fnc) => derive_function(fnc, var),
+        Expr::Unary(UnaryOp::Neg, expr, span) => Ok(neg(derive(expr, var)?, span)),
+        Expr::Unary(UnaryOp::Plus, expr, _) => derive(expr, var),
+        Expr::Unary(UnaryOp::Not, expr, span) => constant("!", &[expr], var, span),
+        Expr::Interval(lo, hi, span) => constant("[lo, hi]", &[lo, hi], var, span),
+    }?;
+    // The derivative of a constant is a `0` spanning the whole constant.
+    if is_zero(&derivative) {
+        return Ok(Expr::Int(0, expr.span().clone()));
+    }
+    Ok(derivative)
+}
+
+fn derive_node(node: &TreeNode, var: &str) -> Result<Expr, Error> {
+    let TreeNode { l_expr: l, op, r_expr: r, span } = node;
+    match op {
+        Operator::Add => Ok(add(derive(l, var)?, derive(r, var)?, span)),
+        Operator::Sub => Ok(sub(derive(l, var)?, derive(r, var)?, span)),
+        // `(uv)' = u'v + uv'`
+        Operator::Mul => {
+            let (dl, dr) = (derive(l, var)?, derive(r, var)?);
+            Ok(add(mul(dl, r.clone(), span), mul(l.clone(), dr, span), span))
+        }
+        // `(u/v)' = (u'v - uv') / v^2`
+        Operator::Div => {
+            let (dl, dr) = (derive(l, var)?, derive(r, var)?);
+            let numerator = sub(mul(dl, r.clone(), span), mul(l.clone(), dr, span), span);
+            Ok(div(numerator, square(r.clone(), span), span))
+        }
+        Operator::Pow => {
+            let (dl, dr) = (derive(l, var)?, derive(r, var)?);
+            let power = || pow(l.clone(), r.clone(), span);
+            if is_zero(&dr) {
+                // `(u^n)' = n u^(n - 1) u'`
+                let exponent = match r {
+                    Expr::Int(n, span) => Expr::Int(n - 1, span.clone()),
+                    Expr::Number(n, _, span) => Expr::Number(n - 1., None, span.clone()),
+                    r => sub(r.clone(), Expr::Int(1, span.clone()), span),
+                };
+                Ok(mul(mul(r.clone(), pow(l.clone(), exponent, span), span), dl, span))
+            } else if is_zero(&dl) {
+                // `(a^v)' = a^v ln(a) v'`
+                Ok(mul(mul(power(), ln(l.clone()), span), dr, span))
+            } else {
+                // `(u^v)' = u^v (v' ln(u) + v u' / u)`
+                let factor = add(mul(dr, ln(l.clone()), span), div(mul(r.clone(), dl, span), l.clone(), span), span);
+                Ok(mul(power(), factor, span))
+            }
+        }
+        op => constant(op, &[l, r], var, span),
+    }
+}
+
+fn derive_function(fnc: &Function, var: &str) -> Result<Expr, Error> {
+    let span = fnc.span();
+    let one = || Expr::Int(1, span.clone());
+    let function = |fnc: Function| Expr::Function(Box::new(fnc));
+    // Chain rule, `f(u)' = f'(u) u'` with `f'(u)` built from `u`.
+    let chain = |u: &Expr, derivative: &dyn Fn(Expr) -> Expr| -> Result<Expr, Error> {
+        let du = derive(u, var)?;
+        if is_zero(&du) {
+            return Ok(du);
+        }
+        Ok(mul(derivative(u.clone()), du, span))
+    };
+    match fnc {
+        Function::Sqrt(u, _) => chain(u, &|u| {
+            div(one(), mul(Expr::Int(2, span.clone()), function(Function::Sqrt(u, span.clone())), span), span)
+        }),
+        Function::Cbrq(u, _) => chain(u, &|u| {
+            let cbrq = function(Function::Cbrq(u, span.clone()));
+            div(one(), mul(Expr::Int(3, span.clone()), square(cbrq, span), span), span)
+        }),
+        // The sign of `u`.
+        Function::Abs(u, _) => chain(u, &|u| div(u.clone(), function(Function::Abs(u, span.clone())), span)),
+        Function::Sin(u, _) => chain(u, &|u| function(Function::Cos(u, span.clone()))),
+        Function::Cos(u, _) => chain(u, &|u| neg(function(Function::Sin(u, span.clone())), span)),
+        Function::Tan(u, _) => chain(u, &|u| div(one(), square(function(Function::Cos(u, span.clone())), span), span)),
+        Function::ArcSin(u, _) => chain(u, &|u| div(one(), sqrt_one_minus_square(u, span), span)),
+        Function::ArcCos(u, _) => chain(u, &|u| neg(div(one(), sqrt_one_minus_square(u, span), span), span)),
+        Function::ArcTan(u, _) => chain(u, &|u| div(one(), add(one(), square(u, span), span), span)),
+        // `atan2(y, x)' = (x y' - y x') / (x^2 + y^2)`
+        Function::ArcTan2(y, x, _) => {
+            let (dy, dx) = (derive(y, var)?, derive(x, var)?);
+            let numerator = sub(mul(x.clone(), dy, span), mul(y.clone(), dx, span), span);
+            Ok(div(numerator, add(square(x.clone(), span), square(y.clone(), span), span), span))
+        }
+        Function::Ln(u, _) => chain(u, &|u| div(one(), u, span)),
+        Function::Log(u, _) => chain(u, &|u| div(one(), mul(u, ln(Expr::Int(10, span.clone())), span), span)),
+        // `logb(u, b)` is `ln(u) / ln(b)`.
+        Function::LogBased(u, base, _) => {
+            let quotient = TreeNode::new(ln(u.clone()), Operator::Div, ln(base.clone()), span.clone());
+            derive_node(&quotient, var)
+        }
+        // Linear functions.
+        Function::Deg(u, _) => linear(derive(u, var)?, |du| Function::Deg(du, span.clone())),
+        Function::Rad(u, _) => linear(derive(u, var)?, |du| Function::Rad(du, span.clone())),
+        Function::Re(u, _) | Function::Conj(u, _) => derive(u, var),
+        Function::Im(..) => Ok(Expr::Int(0, span.clone())),
+        Function::If(condition, then, otherwise, _) => {
+            let (then, otherwise) = (derive(then, var)?, derive(otherwise, var)?);
+            if is_zero(&then) && is_zero(&otherwise) {
+                return Ok(Expr::Int(0, span.clone()));
+            }
+            Ok(function(Function::If(condition.clone(), then, otherwise, span.clone())))
+        }
+        Function::Round(..)
+        | Function::Floor(..)
+        | Function::Ceil(..)
+        | Function::Trunc(..)
+        | Function::Frac(..)
+        | Function::Arg(..)
+        | Function::Max(..)
+        | Function::Min(..)
+        | Function::Custom(..) => constant(fnc.name(), &fnc.args(), var, span),
+    }
+}
+
+// Derivative of an operation without one, `0` when its operands are constant.
+fn constant(name: impl ToString, operands: &[&Expr], var: &str, span: &Span) -> Result<Expr, Error> {
+    for operand in operands {
+        if !is_zero(&derive(operand, var)?) {
+            return Err(Error::new(ErrorKinds::NotDifferentiable(name.to_string()), vec![span.clone()]));
+        }
+    }
+    Ok(Expr::Int(0, span.clone()))
+}
+
+// Derivative of a linear function `f`, which is `f(u')`.
+fn linear(du: Expr, f: impl Fn(Expr) -> Function) -> Result<Expr, Error> {
+    if is_zero(&du) {
+        return Ok(du);
+    }
+    Ok(Expr::Function(Box::new(f(du))))
+}
+
+fn is_zero(expr: &Expr) -> bool {
+    matches!(expr, Expr::Int(0, _)) || matches!(expr, Expr::Number(n, ..) if *n == 0.)
+}
+
+fn is_one(expr: &Expr) -> bool {
+    matches!(expr, Expr::Int(1, _)) || matches!(expr, Expr::Number(n, ..) if *n == 1.)
+}
+
+fn node(l: Expr, op: Operator, r: Expr, span: &Span) -> Expr {
+    Expr::Node(Box::new(TreeNode::new(l, op, r, span.clone())))
+}
+
+fn add(l: Expr, r: Expr, span: &Span) -> Expr {
+    if is_zero(&l) {
+        r
+    } else if is_zero(&r) {
+        l
+    } else {
+        node(l, Operator::Add, r, span)
+    }
+}
+
+fn sub(l: Expr, r: Expr, span: &Span) -> Expr {
+    if is_zero(&r) {
+        l
+    } else if is_zero(&l) {
+        neg(r, span)
+    } else {
+        node(l, Operator::Sub, r, span)
+    }
+}
+
+fn mul(l: Expr, r: Expr, span: &Span) -> Expr {
+    if is_zero(&l) || is_one(&r) {
+        l
+    } else if is_zero(&r) || is_one(&l) {
+        r
+    } else {
+        node(l, Operator::Mul, r, span)
+    }
+}
+
+fn div(l: Expr, r: Expr, span: &Span) -> Expr {
+    if is_zero(&l) || is_one(&r) {
+        l
+    } else {
+        node(l, Operator::Div, r, span)
+    }
+}
+
+fn pow(l: Expr, r: Expr, span: &Span) -> Expr {
+    if is_one(&r) {
+        l
+    } else {
+        node(l, Operator::Pow, r, span)
+    }
+}
+
+fn square(expr: Expr, span: &Span) -> Expr {
+    pow(expr, Expr::Int(2, span.clone()), span)
+}
+
+fn neg(expr: Expr, span: &Span) -> Expr {
+    match expr {
+        expr if is_zero(&expr) => expr,
+        Expr::Unary(UnaryOp::Neg, expr, _) => *expr,
+        expr => Expr::Unary(UnaryOp::Neg, Box::new(expr), span.clone()),
+    }
+}
+
+// `ln(u)`, which is `1` for `e`.
+fn ln(expr: Expr) -> Expr {
+    match expr {
+        Expr::Const(Const::E(span)) => Expr::Int(1, span),
+        expr => {
+            let span = expr.span().clone();
+            Expr::Function(Box::new(Function::Ln(expr, span)))
+        }
+    }
+}
+
+// `sqrt(1 - u^2)`, the derivative of `asin` being its inverse.
+fn sqrt_one_minus_square(u: Expr, span: &Span) -> Expr {
+    let difference = sub(Expr::Int(1, span.clone()), square(u, span), span);
+    Expr::Function(Box::new(Function::Sqrt(difference, span.clone())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Context;
+    use crate::eval::Eval;
+    use crate::value::Value;
+
+    // Value of the derivative by `x` of `input` at `x`.
+    fn derivative_at(input: &str, x: f64) -> f64 {
+        let mut ctx = Context::new();
+        ctx.set_variable("x", x);
+        ctx.set_variable("y", 3.);
+        let derivative = input.parse::<Expr>().unwrap().derive("x").unwrap();
+        derivative.eval_with(&ctx).unwrap().to_number(derivative.span()).unwrap()
+    }
+
+    fn assert_close(l: f64, r: f64) {
+        assert!((l - r).abs() < 1e-12, "{} is not {}", l, r);
+    }
+
+    #[test]
+    fn derive_operators() {
+        let expr = "x^3".parse::<Expr>().unwrap();
+        let pow = node(Expr::Variable("x".to_owned(), 0..1), Operator::Pow, Expr::Int(2, 2..3), &(0..3));
+        assert_eq!(expr.derive("x"), Ok(node(Expr::Int(3, 2..3), Operator::Mul, pow, &(0..3))));
+        assert_eq!("2y + pi".parse::<Expr>().unwrap().derive("x"), Ok(Expr::Int(0, 0..7)));
+        assert_close(derivative_at("3x^2 - 2x + y", 2.), 10.);
+        assert_close(derivative_at("x * y * -x", 2.), -12.);
+        assert_close(derivative_at("(x + 1) / (x - 1)", 3.), -0.5);
+        assert_close(derivative_at("2^x + x^x", 1.), 2. * 2f64.ln() + 1.);
+        assert_close(derivative_at("e^(2x)", 0.5), 2. * std::f64::consts::E);
+    }
+
+    #[test]
+    fn derive_functions() {
+        let x = 0.5f64;
+        assert_close(derivative_at("sin(x)^2", x), 2. * x.sin() * x.cos());
+        assert_close(derivative_at("sqrt(x^2 + 1)", x), x / (x * x + 1.).sqrt());
+        assert_close(derivative_at("tan(2x) + cos(x)", x), 2. / (2. * x).cos().powi(2) - x.sin());
+        assert_close(derivative_at("asin(x) + acos(x) + atan(x)", x), 1. / (1. + x * x));
+        assert_close(derivative_at("atan2(y, x)", x), -3. / (x * x + 9.));
+        assert_close(derivative_at("ln(x) + log(x) + logb(x, 2)", x), (1. + 1. / 10f64.ln() + 1. / 2f64.ln()) / x);
+        assert_close(derivative_at("cbrq(x) + abs(-x)", -8.), 1. / 12. - 1.);
+        assert_close(derivative_at("deg(x) + floor(y) * x", x), 180. / std::f64::consts::PI + 3.);
+        assert_close(derivative_at("if(x > 0, x^2, -x)", x), 1.);
+    }
+
+    #[test]
+    fn derive_without_derivative() {
+        let derive_str = |input: &str| input.parse::<Expr>().unwrap().derive("x");
+        assert_eq!(
+            derive_str("1 + floor(2x)"),
+            Err(Error::new(ErrorKinds::NotDifferentiable("floor".to_owned()), vec![4..13]))
+        );
+        assert_eq!(
+            derive_str("round(x, 2) * 2"),
+            Err(Error::new(ErrorKinds::NotDifferentiable("round".to_owned()), vec![0..11]))
+        );
+        assert_eq!(
+            derive_str("x % 2"),
+            Err(Error::new(ErrorKinds::NotDifferentiable("%".to_owned()), vec![0..5]))
+        );
+        let mut ctx = Context::new();
+        ctx.set_function("double", 1, |args| args[0] * 2.);
+        let expr = Expr::parse_with("double(x)", &ctx).unwrap();
+        assert_eq!(
+            expr.derive("x"),
+            Err(Error::new(ErrorKinds::NotDifferentiable("double".to_owned()), vec![0..9]))
+        );
+        assert_eq!(derive_str("max(1, 2) + x").map(|expr| expr.eval()), Ok(Ok(Value::Int(1))));
+    }
+}
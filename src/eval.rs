@@ -1,7 +1,13 @@
+use crate::context::Context;
 
 pub trait Eval {
     type Output;
     type Err;
 
-    fn eval(&self) -> Result<Self::Output, Self::Err>;
+    fn eval_with(&self, ctx: &Context) -> Result<Self::Output, Self::Err>;
+
+    #[inline]
+    fn eval(&self) -> Result<Self::Output, Self::Err> {
+        self.eval_with(&Context::default())
+    }
 }
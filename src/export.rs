@@ -0,0 +1,365 @@
+use crate::ast::consts::Const;
+use crate::ast::expr::Expr;
+use crate::ast::function::Function;
+use crate::ast::token::operator::{Operator, UnaryOp};
+use crate::ast::tree::TreeNode;
+
+// How tightly the expression binds its operands once rendered. A fraction is
+// grouped by its bar, and a number in scientific notation is a product.
+fn precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Node(node) if node.op == Operator::Div => u8::MAX,
+        Expr::Node(node) => node.op.precedence(),
+        Expr::Unary(..) => Operator::Pow.precedence(),
+        Expr::Int(n, _) if *n < 0 => Operator::Pow.precedence(),
+        Expr::Number(n, ..) if n.is_sign_negative() => Operator::Pow.precedence(),
+        Expr::Number(n, ..) if scientific(*n).1.is_some() => Operator::Mul.precedence(),
+        _ => u8::MAX,
+    }
+}
+
+// Minimum precedence of the left and right operands of `op` to go without brackets.
+fn operand_precedences(op: Operator) -> (u8, u8) {
+    let precedence = op.precedence();
+    if op.is_right_associative() {
+        (precedence + 1, precedence)
+    } else {
+        (precedence, precedence + 1)
+    }
+}
+
+// Whether the base of a power needs brackets, as a fraction or `-x` in `(-x)^2`.
+fn bracket_base(base: &Expr) -> bool {
+    matches!(base, Expr::Node(node) if node.op == Operator::Div) || precedence(base) <= Operator::Pow.precedence()
+}
+
+// Digits of the absolute value of `n`, and its exponent when it is written
+// in scientific notation, as `1e-7`.
+fn scientific(n: f64) -> (String, Option<String>) {
+    let debug = format!("{:?}", n.abs());
+    match debug.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa.to_owned(), Some(exponent.to_owned())),
+        None => (n.abs().to_string(), None),
+    }
+}
+
+/// LaTeX of `expr`, see [`Expr::to_latex`].
+pub(crate) fn latex(expr: &Expr) -> String {
+    match expr {
+        Expr::Node(node) => latex_node(node),
+        Expr::Int(n, _) => n.to_string(),
+        Expr::Number(n, ..) if n.is_nan() => "\\mathrm{NaN}".to_owned(),
+        Expr::Number(n, ..) if n.is_infinite() => format!("{}\\infty", if *n < 0. { "-" } else { "" }),
+        Expr::Number(n, ..) => {
+            let sign = if n.is_sign_negative() { "-" } else { "" };
+            match scientific(*n) {
+                (mantissa, Some(exponent)) => format!("{}{} \\times 10^{{{}}}", sign, mantissa, exponent),
+                (digits, None) => format!("{}{}", sign, digits),
+            }
+        }
+        Expr::Const(c) => latex_const(c),
+        Expr::Variable(name, _) => latex_name(name),
+        Expr::Function(fnc) => latex_function(fnc),
+        Expr::Unary(op, expr, _) => {
+            let op = match op {
+                UnaryOp::Neg => "-",
+                UnaryOp::Plus => "+",
+                UnaryOp::Not => "\\lnot ",
+            };
+            format!("{}{}", op, latex_operand(expr, Operator::Pow.precedence()))
+        }
+        Expr::Interval(lo, hi, _) => format!("\\left[{}, {}\\right]", latex(lo), latex(hi)),
+    }
+}
+
+fn latex_node(node: &TreeNode) -> String {
+    let TreeNode { l_expr: l, op, r_expr: r, .. } = node;
+    let symbol = match op {
+        Operator::Div => return format!("\\frac{{{}}}{{{}}}", latex(l), latex(r)),
+        Operator::Pow => {
+            let base = if bracket_base(l) { format!("\\left({}\\right)", latex(l)) } else { latex(l) };
+            return format!("{}^{{{}}}", base, latex(r));
+        }
+        Operator::Add => "+",
+        Operator::Sub => "-",
+        Operator::Mul => "\\cdot",
+        Operator::Mod => "\\bmod",
+        Operator::FDiv => "\\mathbin{\\mathrm{div}}",
+        Operator::BitAnd => "\\mathbin{\\&}",
+        Operator::BitOr => "\\mathbin{|}",
+        Operator::Xor => "\\oplus",
+        Operator::Shl => "\\ll",
+        Operator::Shr => "\\gg",
+        Operator::Eq => "=",
+        Operator::Ne => "\\neq",
+        Operator::Lt => "<",
+        Operator::Le => "\\leq",
+        Operator::Gt => ">",
+        Operator::Ge => "\\geq",
+        Operator::And => "\\land",
+        Operator::Or => "\\lor",
+        Operator::PlusMinus => "\\pm",
+    };
+    let (l_min, r_min) = operand_precedences(*op);
+    format!("{} {} {}", latex_operand(l, l_min), symbol, latex_operand(r, r_min))
+}
+
+fn latex_operand(expr: &Expr, min_precedence: u8) -> String {
+    if precedence(expr) < min_precedence {
+        format!("\\left({}\\right)", latex(expr))
+    } else {
+        latex(expr)
+    }
+}
+
+fn latex_const(c: &Const) -> String {
+    match c {
+        Const::Pi(_) => "\\pi".to_owned(),
+        Const::E(_) => "e".to_owned(),
+        Const::Inf(_) => "\\infty".to_owned(),
+        Const::Tau(_) => "\\tau".to_owned(),
+        Const::Phi(_) => "\\varphi".to_owned(),
+        Const::NaN(_) => "\\mathrm{NaN}".to_owned(),
+        Const::EulerGamma(_) => "\\gamma".to_owned(),
+        Const::I(_) => "i".to_owned(),
+        Const::Custom(name, _, _) => latex_name(name),
+    }
+}
+
+// A one letter name in italic, a longer one upright, as `x` and `\mathrm{speed}`.
+fn latex_name(name: &str) -> String {
+    let escaped = name.replace('_', "\\_");
+    if name.chars().count() == 1 {
+        escaped
+    } else {
+        format!("\\mathrm{{{}}}", escaped)
+    }
+}
+
+fn latex_function(fnc: &Function) -> String {
+    let args = fnc.args().into_iter().map(latex).collect::<Vec<_>>();
+    let command = match fnc {
+        Function::Sqrt(..) => return format!("\\sqrt{{{}}}", args[0]),
+        Function::Cbrq(..) => return format!("\\sqrt[3]{{{}}}", args[0]),
+        Function::Abs(..) => return format!("\\left|{}\\right|", args[0]),
+        Function::Floor(..) => return format!("\\left\\lfloor {} \\right\\rfloor", args[0]),
+        Function::Ceil(..) => return format!("\\left\\lceil {} \\right\\rceil", args[0]),
+        Function::Conj(..) => return format!("\\overline{{{}}}", args[0]),
+        Function::LogBased(..) => return format!("\\log_{{{}}}\\left({}\\right)", args[1], args[0]),
+        Function::If(..) => {
+            return format!(
+                "\\begin{{cases}} {} & \\text{{if }} {} \\\\ {} & \\text{{otherwise}} \\end{{cases}}",
+                args[1], args[0], args[2]
+            )
+        }
+        Function::Sin(..) => "\\sin".to_owned(),
+        Function::Cos(..) => "\\cos".to_owned(),
+        Function::Tan(..) => "\\tan".to_owned(),
+        Function::ArcSin(..) => "\\arcsin".to_owned(),
+        Function::ArcCos(..) => "\\arccos".to_owned(),
+        Function::ArcTan(..) => "\\arctan".to_owned(),
+        Function::Ln(..) => "\\ln".to_owned(),
+        Function::Log(..) => "\\log_{10}".to_owned(),
+        Function::Max(..) => "\\max".to_owned(),
+        Function::Min(..) => "\\min".to_owned(),
+        Function::Arg(..) => "\\arg".to_owned(),
+        Function::Re(..) => "\\operatorname{Re}".to_owned(),
+        Function::Im(..) => "\\operatorname{Im}".to_owned(),
+        fnc => format!("\\operatorname{{{}}}", fnc.name().replace('_', "\\_")),
+    };
+    format!("{}\\left({}\\right)", command, args.join(", "))
+}
+
+/// Presentation MathML of `expr`, without the `math` element, see
+/// [`Expr::to_mathml`].
+pub(crate) fn mathml(expr: &Expr) -> String {
+    match expr {
+        Expr::Node(node) => mathml_node(node),
+        Expr::Int(n, _) if *n < 0 => format!("<mrow><mo>-</mo><mn>{}</mn></mrow>", n.unsigned_abs()),
+        Expr::Int(n, _) => format!("<mn>{}</mn>", n),
+        Expr::Number(n, ..) if n.is_nan() => "<mi>NaN</mi>".to_owned(),
+        Expr::Number(n, ..) if n.is_infinite() => {
+            if *n < 0. { "<mrow><mo>-</mo><mi>∞</mi></mrow>".to_owned() } else { "<mi>∞</mi>".to_owned() }
+        }
+        Expr::Number(n, ..) => {
+            let number = match scientific(*n) {
+                (digits, None) if !n.is_sign_negative() => return format!("<mn>{}</mn>", digits),
+                (digits, None) => format!("<mn>{}</mn>", digits),
+                (mantissa, Some(exponent)) => {
+                    format!("<mn>{}</mn><mo>×</mo><msup><mn>10</mn><mn>{}</mn></msup>", mantissa, exponent)
+                }
+            };
+            format!("<mrow>{}{}</mrow>", if n.is_sign_negative() { "<mo>-</mo>" } else { "" }, number)
+        }
+        Expr::Const(c) => mathml_const(c),
+        Expr::Variable(name, _) => format!("<mi>{}</mi>", escape(name)),
+        Expr::Function(fnc) => mathml_function(fnc),
+        Expr::Unary(op, expr, _) => {
+            let op = match op {
+                UnaryOp::Neg => "-",
+                UnaryOp::Plus => "+",
+                UnaryOp::Not => "¬",
+            };
+            format!("<mrow><mo>{}</mo>{}</mrow>", op, mathml_operand(expr, Operator::Pow.precedence()))
+        }
+        Expr::Interval(lo, hi, _) => {
+            format!("<mrow><mo>[</mo>{}<mo>,</mo>{}<mo>]</mo></mrow>", mathml(lo), mathml(hi))
+        }
+    }
+}
+
+fn mathml_node(node: &TreeNode) -> String {
+    let TreeNode { l_expr: l, op, r_expr: r, .. } = node;
+    let symbol = match op {
+        Operator::Div => return format!("<mfrac>{}{}</mfrac>", mathml(l), mathml(r)),
+        Operator::Pow => {
+            let base = if bracket_base(l) { brackets(&mathml(l)) } else { mathml(l) };
+            return format!("<msup>{}{}</msup>", base, mathml(r));
+        }
+        Operator::Add => "+",
+        Operator::Sub => "-",
+        Operator::Mul => "⋅",
+        Operator::Mod => "mod",
+        Operator::FDiv => "div",
+        Operator::BitAnd => "&amp;",
+        Operator::BitOr => "|",
+        Operator::Xor => "⊕",
+        Operator::Shl => "≪",
+        Operator::Shr => "≫",
+        Operator::Eq => "=",
+        Operator::Ne => "≠",
+        Operator::Lt => "&lt;",
+        Operator::Le => "≤",
+        Operator::Gt => "&gt;",
+        Operator::Ge => "≥",
+        Operator::And => "∧",
+        Operator::Or => "∨",
+        Operator::PlusMinus => "±",
+    };
+    let (l_min, r_min) = operand_precedences(*op);
+    format!("<mrow>{}<mo>{}</mo>{}</mrow>", mathml_operand(l, l_min), symbol, mathml_operand(r, r_min))
+}
+
+fn mathml_operand(expr: &Expr, min_precedence: u8) -> String {
+    if precedence(expr) < min_precedence {
+        brackets(&mathml(expr))
+    } else {
+        mathml(expr)
+    }
+}
+
+fn brackets(mathml: &str) -> String {
+    format!("<mrow><mo>(</mo>{}<mo>)</mo></mrow>", mathml)
+}
+
+fn mathml_const(c: &Const) -> String {
+    let name = match c {
+        Const::Pi(_) => "π",
+        Const::E(_) => "e",
+        Const::Inf(_) => "∞",
+        Const::Tau(_) => "τ",
+        Const::Phi(_) => "φ",
+        Const::NaN(_) => "NaN",
+        Const::EulerGamma(_) => "γ",
+        Const::I(_) => "i",
+        Const::Custom(name, _, _) => return format!("<mi>{}</mi>", escape(name)),
+    };
+    format!("<mi>{}</mi>", name)
+}
+
+fn mathml_function(fnc: &Function) -> String {
+    let args = fnc.args().into_iter().map(mathml).collect::<Vec<_>>();
+    let name = match fnc {
+        Function::Sqrt(..) => return format!("<msqrt>{}</msqrt>", args[0]),
+        Function::Cbrq(..) => return format!("<mroot>{}<mn>3</mn></mroot>", args[0]),
+        Function::Abs(..) => return format!("<mrow><mo>|</mo>{}<mo>|</mo></mrow>", args[0]),
+        Function::Floor(..) => return format!("<mrow><mo>⌊</mo>{}<mo>⌋</mo></mrow>", args[0]),
+        Function::Ceil(..) => return format!("<mrow><mo>⌈</mo>{}<mo>⌉</mo></mrow>", args[0]),
+        Function::Conj(..) => return format!("<mover>{}<mo>¯</mo></mover>", args[0]),
+        Function::If(..) => {
+            return format!(
+                "<mrow><mo>{{</mo><mtable><mtr><mtd>{}</mtd><mtd><mtext>if </mtext>{}</mtd></mtr>\
+                 <mtr><mtd>{}</mtd><mtd><mtext>otherwise</mtext></mtd></mtr></mtable></mrow>",
+                args[1], args[0], args[2]
+            )
+        }
+        Function::LogBased(..) => {
+            let log = format!("<msub><mi>log</mi>{}</msub>", args[1]);
+            return format!("<mrow>{}<mo>&#x2061;</mo>{}</mrow>", log, brackets(&args[0]));
+        }
+        Function::Log(..) => "<msub><mi>log</mi><mn>10</mn></msub>".to_owned(),
+        Function::ArcSin(..) => "<mi>arcsin</mi>".to_owned(),
+        Function::ArcCos(..) => "<mi>arccos</mi>".to_owned(),
+        Function::ArcTan(..) => "<mi>arctan</mi>".to_owned(),
+        Function::Re(..) => "<mi>Re</mi>".to_owned(),
+        Function::Im(..) => "<mi>Im</mi>".to_owned(),
+        fnc => format!("<mi>{}</mi>", escape(fnc.name())),
+    };
+    let args = args.join("<mo>,</mo>");
+    format!("<mrow>{}<mo>&#x2061;</mo>{}</mrow>", name, brackets(&args))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latex_str(input: &str) -> String {
+        input.parse::<Expr>().unwrap().to_latex()
+    }
+
+    fn mathml_str(input: &str) -> String {
+        mathml(&input.parse::<Expr>().unwrap())
+    }
+
+    #[test]
+    fn export_latex() {
+        assert_eq!(latex_str("(1 + x) / 2 * pi"), "\\frac{1 + x}{2} \\cdot \\pi");
+        assert_eq!(latex_str("(-x)^(2 + y) ^ 3"), "\\left(-x\\right)^{\\left(2 + y\\right)^{3}}");
+        assert_eq!(latex_str("sqrt(x) + cbrq(8) - abs(x - 1)"), "\\sqrt{x} + \\sqrt[3]{8} - \\left|x - 1\\right|");
+        assert_eq!(latex_str("logb(x, 2) * sin(x)^2"), "\\log_{2}\\left(x\\right) \\cdot \\sin\\left(x\\right)^{2}");
+        assert_eq!(
+            latex_str("(1 / 2)^x + 1.5e-7 * speed"),
+            "\\left(\\frac{1}{2}\\right)^{x} + 1.5 \\times 10^{-7} \\cdot \\mathrm{speed}"
+        );
+        assert_eq!(
+            latex_str("-(a - b) <= floor(atan2(y, x))"),
+            "-\\left(a - b\\right) \\leq \\left\\lfloor \\operatorname{atan2}\\left(y, x\\right) \\right\\rfloor"
+        );
+        assert_eq!(
+            latex_str("if(x > 0, x, 0)"),
+            "\\begin{cases} x & \\text{if } x > 0 \\\\ 0 & \\text{otherwise} \\end{cases}"
+        );
+    }
+
+    #[test]
+    fn export_mathml() {
+        assert_eq!(
+            mathml_str("(1 + x) / 2 * pi"),
+            "<mrow><mfrac><mrow><mn>1</mn><mo>+</mo><mi>x</mi></mrow><mn>2</mn></mfrac><mo>⋅</mo><mi>π</mi></mrow>"
+        );
+        assert_eq!(
+            mathml_str("(-x)^2 < cbrq(y)"),
+            "<mrow><msup><mrow><mo>(</mo><mrow><mo>-</mo><mi>x</mi></mrow><mo>)</mo></mrow><mn>2</mn></msup>\
+             <mo>&lt;</mo><mroot><mi>y</mi><mn>3</mn></mroot></mrow>"
+        );
+        assert_eq!(
+            mathml_str("logb(x, 2)"),
+            "<mrow><msub><mi>log</mi><mn>2</mn></msub><mo>&#x2061;</mo><mrow><mo>(</mo><mi>x</mi><mo>)</mo></mrow></mrow>"
+        );
+        assert_eq!(
+            mathml_str("max(abs(x), 2.5)"),
+            "<mrow><mi>max</mi><mo>&#x2061;</mo><mrow><mo>(</mo><mrow><mo>|</mo><mi>x</mi><mo>|</mo></mrow>\
+             <mo>,</mo><mn>2.5</mn><mo>)</mo></mrow></mrow>"
+        );
+        let expr = "sqrt(2) * 1e300".parse::<Expr>().unwrap();
+        assert_eq!(
+            expr.to_mathml(),
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mrow><msqrt><mn>2</mn></msqrt><mo>⋅</mo>\
+             <mrow><mo>(</mo><mrow><mn>1</mn><mo>×</mo><msup><mn>10</mn><mn>300</mn></msup></mrow><mo>)</mo></mrow></mrow></math>"
+        );
+    }
+}
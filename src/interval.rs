@@ -0,0 +1,626 @@
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+use std::fmt;
+
+use crate::ast::consts::Const;
+use crate::ast::expr::Expr;
+use crate::ast::function::Function;
+use crate::ast::token::operator::{Operator, UnaryOp};
+use crate::ast::tree::TreeNode;
+use crate::context::Context;
+use crate::eval::Eval;
+use crate::maph_error::{Error, ErrorKinds, Span};
+use crate::numeric::Numeric;
+use crate::value::Value;
+
+/// A closed interval `[lo, hi]` holding every possible result of an
+/// expression, see [`Expr::eval_interval`].
+///
+/// Bounds are rounded outward, so the exact result of the expression on any
+/// number of the input intervals is guaranteed to lie in the interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+impl Interval {
+    /// The interval `[lo, hi]`, `lo` being lower than or equal to `hi`.
+    #[inline]
+    pub fn new(lo: f64, hi: f64) -> Self {
+        Self { lo, hi }
+    }
+
+    #[inline]
+    pub fn point(n: f64) -> Self {
+        Self::new(n, n)
+    }
+
+    #[inline]
+    pub fn contains(&self, n: f64) -> bool {
+        self.lo <= n && n <= self.hi
+    }
+
+    #[inline]
+    pub fn is_point(&self) -> bool {
+        self.lo == self.hi
+    }
+
+    pub fn width(&self) -> f64 {
+        up(sub(self.hi, self.lo))
+    }
+
+    // Image of a non-decreasing function computed exactly.
+    fn map(self, f: impl Fn(f64) -> f64) -> Self {
+        Self::new(f(self.lo), f(self.hi))
+    }
+
+    // Image of a non-decreasing function computed within an ulp.
+    fn increasing(self, f: impl Fn(f64) -> f64) -> Self {
+        Self::new(down(widen(f(self.lo))), up(widen(f(self.hi))))
+    }
+
+    // Image of a non-increasing function computed within an ulp.
+    fn decreasing(self, f: impl Fn(f64) -> f64) -> Self {
+        Self::new(down(widen(f(self.hi))), up(widen(f(self.lo))))
+    }
+
+    // Hull of the images of the bounds by a function computed within an ulp.
+    fn endpoints(self, f: impl Fn(f64) -> f64) -> Self {
+        self.increasing(&f).hull(self.decreasing(&f))
+    }
+
+    // Whether the interval is at least as wide as `period`, or unbounded.
+    fn covers(&self, period: f64) -> bool {
+        !self.lo.is_finite() || !self.hi.is_finite() || self.hi - self.lo >= period
+    }
+
+    // Whether the interval holds `offset + k·period` for some integer `k`,
+    // with a margin for the rounding of `offset`.
+    fn contains_periodic(&self, offset: f64, period: f64) -> bool {
+        const MARGIN: f64 = 1e-9;
+        ((self.lo - offset) / period - MARGIN).ceil() <= ((self.hi - offset) / period + MARGIN).floor()
+    }
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(down(add(self.lo, rhs.lo)), up(add(self.hi, rhs.hi)))
+    }
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(down(sub(self.lo, rhs.hi)), up(sub(self.hi, rhs.lo)))
+    }
+
+    fn mul(self, rhs: Self) -> Self {
+        hull_of([mul(self.lo, rhs.lo), mul(self.lo, rhs.hi), mul(self.hi, rhs.lo), mul(self.hi, rhs.hi)])
+    }
+
+    // Division by an interval without zero.
+    fn div(self, rhs: Self) -> Self {
+        hull_of([div(self.lo, rhs.lo), div(self.lo, rhs.hi), div(self.hi, rhs.lo), div(self.hi, rhs.hi)])
+    }
+
+    fn neg(self) -> Self {
+        Self::new(-self.hi, -self.lo)
+    }
+
+    fn abs(self) -> Self {
+        if self.lo >= 0. {
+            self
+        } else if self.hi <= 0. {
+            self.neg()
+        } else {
+            Self::new(0., self.hi.max(-self.lo))
+        }
+    }
+
+    fn sin(self) -> Self {
+        if self.covers(TAU) {
+            return Self::new(-1., 1.);
+        }
+        let Self { mut lo, mut hi } = self.endpoints(f64::sin);
+        if self.contains_periodic(FRAC_PI_2, TAU) {
+            hi = 1.;
+        }
+        if self.contains_periodic(-FRAC_PI_2, TAU) {
+            lo = -1.;
+        }
+        Self::new(lo.max(-1.), hi.min(1.))
+    }
+
+    fn cos(self) -> Self {
+        if self.covers(TAU) {
+            return Self::new(-1., 1.);
+        }
+        let Self { mut lo, mut hi } = self.endpoints(f64::cos);
+        if self.contains_periodic(0., TAU) {
+            hi = 1.;
+        }
+        if self.contains_periodic(PI, TAU) {
+            lo = -1.;
+        }
+        Self::new(lo.max(-1.), hi.min(1.))
+    }
+
+    fn tan(self) -> Self {
+        if self.covers(PI) || self.contains_periodic(FRAC_PI_2, PI) {
+            return Self::new(f64::NEG_INFINITY, f64::INFINITY);
+        }
+        self.increasing(f64::tan)
+    }
+
+    fn hull(self, rhs: Self) -> Self {
+        Self::new(self.lo.min(rhs.lo), self.hi.max(rhs.hi))
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {}]", self.lo, self.hi)
+    }
+}
+
+// Bounds of the exact result of an operation, rounded to `result`, from the
+// sign of its rounding error.
+fn bounds(result: f64, error: f64) -> (f64, f64) {
+    if error > 0. {
+        (result, next_up(result))
+    } else if error < 0. {
+        (next_down(result), result)
+    } else {
+        (result, result)
+    }
+}
+
+// Smallest float greater than `n`, as `f64::next_up` which needs Rust 1.86.
+fn next_up(n: f64) -> f64 {
+    if n.is_nan() || n == f64::INFINITY {
+        return n;
+    }
+    if n == 0. {
+        return f64::from_bits(1);
+    }
+    let bits = n.to_bits();
+    f64::from_bits(if n > 0. { bits + 1 } else { bits - 1 })
+}
+
+fn next_down(n: f64) -> f64 {
+    -next_up(-n)
+}
+
+// Bounds of a finite result which overflowed to an infinity.
+fn overflow(result: f64) -> (f64, f64) {
+    if result > 0. { (f64::MAX, result) } else { (result, f64::MIN) }
+}
+
+fn add(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    if !sum.is_finite() {
+        return if a.is_finite() && b.is_finite() { overflow(sum) } else { (sum, sum) };
+    }
+    let b_part = sum - a;
+    bounds(sum, (a - (sum - b_part)) + (b - b_part))
+}
+
+fn sub(a: f64, b: f64) -> (f64, f64) {
+    add(a, -b)
+}
+
+fn mul(a: f64, b: f64) -> (f64, f64) {
+    // `0 * inf` is `0` for bounds.
+    if a == 0. || b == 0. {
+        return (0., 0.);
+    }
+    let product = a * b;
+    if !product.is_finite() {
+        return if a.is_finite() && b.is_finite() { overflow(product) } else { (product, product) };
+    }
+    bounds(product, a.mul_add(b, -product))
+}
+
+fn div(a: f64, b: f64) -> (f64, f64) {
+    let quotient = a / b;
+    if !quotient.is_finite() {
+        return if a.is_finite() { overflow(quotient) } else { (quotient, quotient) };
+    }
+    if !b.is_finite() {
+        return (quotient, quotient);
+    }
+    let rem = (-quotient).mul_add(b, a);
+    bounds(quotient, if b < 0. { -rem } else { rem })
+}
+
+fn sqrt(n: f64) -> (f64, f64) {
+    let root = n.sqrt();
+    if !root.is_finite() {
+        return (root, root);
+    }
+    bounds(root, (-root).mul_add(root, n))
+}
+
+// Bounds of the result of a function computed within an ulp.
+fn widen(n: f64) -> (f64, f64) {
+    if n.is_finite() { (next_down(n), next_up(n)) } else { (n, n) }
+}
+
+#[inline]
+fn down((lo, _): (f64, f64)) -> f64 {
+    lo
+}
+
+#[inline]
+fn up((_, hi): (f64, f64)) -> f64 {
+    hi
+}
+
+fn hull_of(bounds: [(f64, f64); 4]) -> Interval {
+    bounds
+        .iter()
+        .fold(Interval::new(f64::INFINITY, f64::NEG_INFINITY), |acc, (lo, hi)| {
+            Interval::new(acc.lo.min(*lo), acc.hi.max(*hi))
+        })
+}
+
+// Whether the number written as the shortest form of `n` is exactly `n`,
+// as `0.5` but not `0.1`.
+fn is_exact(n: f64) -> bool {
+    if n.fract() == 0. || !n.is_finite() {
+        return true;
+    }
+    let written = n.abs().to_string();
+    let (int, frac) = written.split_once('.').unwrap_or((&written, ""));
+    let digits = format!("{}{}", int, frac).parse::<u128>();
+    let five_pow = 5u128.checked_pow(frac.len() as u32);
+    matches!((digits, five_pow), (Ok(digits), Some(five_pow)) if digits % five_pow == 0)
+}
+
+// Interval around a number rounded to the nearest float.
+fn approximate(n: f64) -> Interval {
+    let (lo, hi) = widen(n);
+    Interval::new(lo, hi)
+}
+
+// Evaluator over intervals. Spans are only used for errors.
+pub(crate) fn eval(expr: &Expr, ctx: &Context) -> Result<Interval, Error> {
+    match expr {
+        Expr::Node(node) => eval_node(node, ctx),
+        // A literal with more digits than its float, or out of its range, lies
+        // between the neighbours of the float, and is never negative.
+        Expr::Number(n, Some(_), _) => Ok(Interval::new(next_down(*n).max(0.), next_up(*n))),
+        Expr::Number(n, ..) if is_exact(*n) => Ok(Interval::point(*n)),
+        Expr::Number(n, ..) => Ok(approximate(*n)),
+        Expr::Int(n, _) if (*n as f64) as i128 == *n as i128 => Ok(Interval::point(*n as f64)),
+        Expr::Int(n, _) => Ok(approximate(*n as f64)),
+        // A custom constant is exact, as a variable.
+        Expr::Const(Const::Custom(_, value, _)) => Ok(Interval::point(*value)),
+        Expr::Const(c) => Ok(approximate(c.eval_with(ctx)?.to_number(c.span())?)),
+        Expr::Function(fnc) => eval_function(fnc, ctx),
+        Expr::Variable(..) => Ok(Interval::point(expr.eval_with(ctx)?.to_number(expr.span())?)),
+        Expr::Unary(UnaryOp::Neg, expr, _) => Ok(eval(expr, ctx)?.neg()),
+        Expr::Unary(UnaryOp::Plus, expr, _) => eval(expr, ctx),
+        Expr::Unary(UnaryOp::Not, _, span) => Err(unsupported(UnaryOp::Not, span)),
+        Expr::Interval(lo, hi, span) => {
+            let (lo, hi) = (eval(lo, ctx)?, eval(hi, ctx)?);
+            if lo.lo > hi.hi {
+                return Err(Error::new(ErrorKinds::EmptyInterval, vec![span.clone()]));
+            }
+            Ok(Interval::new(lo.lo, hi.hi))
+        }
+    }
+}
+
+fn eval_node(node: &TreeNode, ctx: &Context) -> Result<Interval, Error> {
+    let l = eval(&node.l_expr, ctx)?;
+    let r = eval(&node.r_expr, ctx)?;
+    let span = &node.span;
+    let r_span = node.r_expr.span();
+    match node.op {
+        Operator::Add => Ok(l.add(r)),
+        Operator::Sub => Ok(l.sub(r)),
+        Operator::Mul => Ok(l.mul(r)),
+        Operator::PlusMinus => {
+            let tolerance = r.abs();
+            Ok(Interval::new(down(sub(l.lo, tolerance.hi)), up(add(l.hi, tolerance.hi))))
+        }
+        Operator::Div if r.contains(0.) => Err(Error::new(ErrorKinds::DivisionByZero, vec![r_span.clone()])),
+        Operator::Div => Ok(l.div(r)),
+        Operator::Pow => pow(l, r, span, r_span),
+        Operator::Mod | Operator::FDiv if r.contains(0.) => {
+            Err(Error::new(ErrorKinds::ModuloByZero, vec![r_span.clone()]))
+        }
+        Operator::FDiv => Ok(l.div(r).map(f64::trunc)),
+        Operator::Mod => {
+            let period = r.abs().hi;
+            // `%` on floats is exact, but not the shift of a negative remainder.
+            let rem = |n: f64| match n % period {
+                rem if rem < 0. => add(rem, period),
+                rem => (rem, rem),
+            };
+            // Within a single period, the remainder is increasing.
+            let (lo, hi) = (down(rem(l.lo)), up(rem(l.hi)));
+            if r.is_point() && (l.lo / period).floor() == (l.hi / period).floor() && lo <= hi {
+                return Ok(Interval::new(lo.max(0.), hi.min(period)));
+            }
+            Ok(Interval::new(0., period))
+        }
+        op => Err(unsupported(op, span)),
+    }
+}
+
+fn pow(base: Interval, exp: Interval, span: &Span, exp_span: &Span) -> Result<Interval, Error> {
+    if exp.is_point() && exp.lo.fract() == 0. && exp.lo.abs() <= u32::MAX as f64 {
+        let n = exp.lo.abs() as u32;
+        let pow = if n % 2 == 1 {
+            // An odd power is increasing, and `(-x)^n` is `-(x^n)`.
+            let signed = |x: f64| if x < 0. { powi(-x, n).neg() } else { powi(x, n) };
+            Interval::new(signed(base.lo).lo, signed(base.hi).hi)
+        } else {
+            let abs = base.abs();
+            Interval::new(powi(abs.lo, n).lo, powi(abs.hi, n).hi)
+        };
+        if exp.lo >= 0. {
+            return Ok(pow);
+        }
+        if pow.contains(0.) {
+            return Err(Error::new(ErrorKinds::DivisionByZero, vec![exp_span.clone()]));
+        }
+        return Ok(Interval::point(1.).div(pow));
+    }
+    if base.lo < 0. {
+        return Err(Error::new(ErrorKinds::NotInRange(0., f64::INFINITY), vec![span.clone()]));
+    }
+    // `x^y` is monotonic in both `x` and `y` for a non-negative `x`.
+    let corners = [(base.lo, exp.lo), (base.lo, exp.hi), (base.hi, exp.lo), (base.hi, exp.hi)];
+    Ok(hull_of(corners.map(|(x, y)| widen(x.powf(y)))))
+}
+
+// Bounds of `x^n` for a non-negative `x`, by squaring.
+fn powi(x: f64, mut n: u32) -> Interval {
+    let (mut base, mut pow) = (Interval::point(x), Interval::point(1.));
+    while n > 0 {
+        if n & 1 == 1 {
+            pow = pow.mul(base);
+        }
+        base = base.mul(base);
+        n >>= 1;
+    }
+    pow
+}
+
+fn eval_function(fnc: &Function, ctx: &Context) -> Result<Interval, Error> {
+    let span = fnc.span();
+    let arg = |expr: &Expr| eval(expr, ctx);
+    let in_range = |n: Interval, min: f64, max: f64| {
+        if min <= n.lo && n.hi <= max {
+            Ok(n)
+        } else {
+            Err(Error::new(ErrorKinds::NotInRange(min, max), vec![span.clone()]))
+        }
+    };
+    match fnc {
+        Function::Sqrt(expr, _) => {
+            let n = arg(expr)?;
+            if n.lo < 0. {
+                return Err(Error::new(ErrorKinds::NegativeSqrt, vec![span.clone()]));
+            }
+            Ok(Interval::new(down(sqrt(n.lo)), up(sqrt(n.hi))))
+        }
+        Function::Cbrq(expr, _) => Ok(arg(expr)?.increasing(f64::cbrt)),
+        Function::Abs(expr, _) => Ok(arg(expr)?.abs()),
+        Function::Sin(expr, _) => Ok(arg(expr)?.sin()),
+        Function::Cos(expr, _) => Ok(arg(expr)?.cos()),
+        Function::Tan(expr, _) => Ok(arg(expr)?.tan()),
+        Function::ArcSin(expr, _) => Ok(in_range(arg(expr)?, -1., 1.)?.increasing(f64::asin)),
+        Function::ArcCos(expr, _) => Ok(in_range(arg(expr)?, -1., 1.)?.decreasing(f64::acos)),
+        Function::ArcTan(expr, _) => Ok(arg(expr)?.increasing(f64::atan)),
+        Function::ArcTan2(y, x, _) => {
+            let (y, x) = (arg(y)?, arg(x)?);
+            // Around the origin or across the negative real axis, any angle is possible.
+            if y.contains(0.) && x.lo <= 0. {
+                return Ok(Interval::new(-approximate(PI).hi, approximate(PI).hi));
+            }
+            let corners = [(y.lo, x.lo), (y.lo, x.hi), (y.hi, x.lo), (y.hi, x.hi)];
+            Ok(hull_of(corners.map(|(y, x)| widen(y.atan2(x)))))
+        }
+        Function::Ln(expr, _) => Ok(in_range(arg(expr)?, 0., f64::INFINITY)?.increasing(f64::ln)),
+        Function::Log(expr, _) => Ok(in_range(arg(expr)?, 0., f64::INFINITY)?.increasing(f64::log10)),
+        Function::LogBased(expr, base, _) => {
+            let n = in_range(arg(expr)?, 0., f64::INFINITY)?.increasing(f64::ln);
+            let base_ln = in_range(arg(base)?, 0., f64::INFINITY)?.increasing(f64::ln);
+            if base_ln.contains(0.) {
+                return Err(Error::new(ErrorKinds::DivisionByZero, vec![base.span().clone()]));
+            }
+            Ok(n.div(base_ln))
+        }
+        Function::Round(expr, digits, _) => {
+            let digits = match digits {
+                Some(digits) => digits.eval_with(ctx)?.to_integer(digits.span())?,
+                None => 0,
+            };
+            let n = arg(expr)?;
+            let round = |n: f64| n.round_to(digits, ctx.rounding()).unwrap_or(f64::NAN);
+            if digits == 0 {
+                return Ok(Interval::new(round(n.lo), round(n.hi)));
+            }
+            // A multiple of `10^-digits`, as `0.3`, is not exact in binary.
+            Ok(Interval::new(down(widen(round(n.lo))), up(widen(round(n.hi)))))
+        }
+        Function::Floor(expr, _) => Ok(arg(expr)?.map(f64::floor)),
+        Function::Ceil(expr, _) => Ok(arg(expr)?.map(f64::ceil)),
+        Function::Trunc(expr, _) => Ok(arg(expr)?.map(f64::trunc)),
+        Function::Frac(expr, _) => {
+            let n = arg(expr)?;
+            // Within `(k, k + 1)`, or `(-1, 1)` around zero, `frac(x)` is `x - k`.
+            if n.lo.trunc() == n.hi.trunc() {
+                return Ok(n.map(f64::fract));
+            }
+            Ok(Interval::new(if n.lo < 0. { -1. } else { 0. }, if n.hi > 0. { 1. } else { 0. }))
+        }
+        Function::Deg(expr, _) => Ok(arg(expr)?.increasing(f64::to_degrees)),
+        Function::Rad(expr, _) => Ok(arg(expr)?.increasing(f64::to_radians)),
+        Function::Max(args, _) | Function::Min(args, _) => {
+            let is_max = matches!(fnc, Function::Max(..));
+            let mut result = arg(&args[0])?;
+            for expr in &args[1..] {
+                let n = arg(expr)?;
+                result = if is_max {
+                    Interval::new(result.lo.max(n.lo), result.hi.max(n.hi))
+                } else {
+                    Interval::new(result.lo.min(n.lo), result.hi.min(n.hi))
+                };
+            }
+            Ok(result)
+        }
+        Function::Re(expr, _) | Function::Conj(expr, _) => arg(expr),
+        Function::Im(expr, _) => arg(expr).map(|_| Interval::point(0.)),
+        Function::Arg(expr, _) => {
+            let n = arg(expr)?;
+            if n.lo >= 0. {
+                Ok(Interval::point(0.))
+            } else if n.hi < 0. {
+                Ok(approximate(PI))
+            } else {
+                Ok(Interval::new(0., approximate(PI).hi))
+            }
+        }
+        Function::If(condition, then, otherwise, _) => {
+            let branch = if eval_condition(condition, ctx)? { then } else { otherwise };
+            eval(branch, ctx)
+        }
+        // The variations of a custom function are unknown.
+        Function::Custom(function, args, _) => {
+            for expr in args {
+                if !arg(expr)?.is_point() {
+                    return Err(unsupported(&function.name, span));
+                }
+            }
+            Ok(approximate(fnc.eval_with(ctx)?.to_number(span)?))
+        }
+    }
+}
+
+// Evaluate the condition of an `if`, which must hold for the whole interval or for none of it.
+fn eval_condition(expr: &Expr, ctx: &Context) -> Result<bool, Error> {
+    let uncertain = |span: &Span| Error::new(ErrorKinds::UncertainCondition, vec![span.clone()]);
+    match expr {
+        Expr::Node(node) if node.op == Operator::And => {
+            Ok(eval_condition(&node.l_expr, ctx)? && eval_condition(&node.r_expr, ctx)?)
+        }
+        Expr::Node(node) if node.op == Operator::Or => {
+            Ok(eval_condition(&node.l_expr, ctx)? || eval_condition(&node.r_expr, ctx)?)
+        }
+        Expr::Node(node) if node.op.is_boolean() => {
+            let l = eval(&node.l_expr, ctx)?;
+            let r = eval(&node.r_expr, ctx)?;
+            // Whether `l < r` for all of the numbers, for none of them, or `None`.
+            let less = |l: Interval, r: Interval, or_equal: bool| {
+                if l.hi < r.lo || (or_equal && l.hi == r.lo) {
+                    Some(true)
+                } else if l.lo > r.hi || (!or_equal && l.lo == r.hi) {
+                    Some(false)
+                } else {
+                    None
+                }
+            };
+            let equal = if l.is_point() && l == r {
+                Some(true)
+            } else if l.hi < r.lo || r.hi < l.lo {
+                Some(false)
+            } else {
+                None
+            };
+            let holds = match node.op {
+                Operator::Eq => equal,
+                Operator::Ne => equal.map(|equal| !equal),
+                Operator::Lt => less(l, r, false),
+                Operator::Le => less(l, r, true),
+                Operator::Gt => less(r, l, false),
+                _ => less(r, l, true),
+            };
+            holds.ok_or_else(|| uncertain(&node.span))
+        }
+        Expr::Unary(UnaryOp::Not, expr, _) => Ok(!eval_condition(expr, ctx)?),
+        Expr::Variable(name, _) if matches!(ctx.get_variable(name), Some(Value::Bool(_))) => {
+            Ok(ctx.get_variable(name).is_some_and(|value| value.is_truthy()))
+        }
+        expr => {
+            let n = eval(expr, ctx)?;
+            if !n.contains(0.) {
+                Ok(true)
+            } else if n.is_point() {
+                Ok(false)
+            } else {
+                Err(uncertain(expr.span()))
+            }
+        }
+    }
+}
+
+fn unsupported(op: impl ToString, span: &Span) -> Error {
+    Error::new(ErrorKinds::Unsupported(op.to_string()), vec![span.clone()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval_str(input: &str) -> Result<Interval, Error> {
+        let ctx = Context::new();
+        Expr::parse_with(input, &ctx).unwrap().eval_interval(&ctx)
+    }
+
+    #[test]
+    fn interval_arithmetic() {
+        let n = eval_str("5±0.1").unwrap();
+        assert!(n.lo <= 4.9 && 5.1 <= n.hi && n.width() < 0.2 + 1e-14);
+        assert_eq!(eval_str("1 + 2 * 3"), Ok(Interval::point(7.)));
+        assert_eq!(eval_str("[1.5, 2] * 2 - [0, 1]"), Ok(Interval::new(2., 4.)));
+        assert_eq!(eval_str("[-2, 3]^2 + [-2, 3]^3"), Ok(Interval::new(-8., 36.)));
+        assert_eq!(eval_str("1 / [2, 4]"), Ok(Interval::new(0.25, 0.5)));
+        let third = eval_str("1 / 3").unwrap();
+        assert!(third.contains(1. / 3.));
+        assert_eq!(third.hi, next_up(third.lo));
+        assert_eq!(eval_str("7 % [3, 3] + 7 // 2"), Ok(Interval::new(4., 4.)));
+    }
+
+    #[test]
+    fn interval_long_literals() {
+        let tiny = eval_str("1e-400").unwrap();
+        assert!(tiny.lo == 0. && tiny.hi > 0.);
+        let half = eval_str("0.50000000000000000001").unwrap();
+        assert!(half.lo < 0.5 && 0.5 < half.hi);
+        assert_eq!(eval_str("1e400"), Ok(Interval::new(f64::MAX, f64::INFINITY)));
+    }
+
+    #[test]
+    fn interval_functions() {
+        let sin = eval_str("sin([0, 4])").unwrap();
+        assert_eq!(sin.hi, 1.);
+        assert!(sin.lo < 4f64.sin() && 4f64.sin() < next_up(next_up(sin.lo)));
+        assert_eq!(eval_str("cos([-1, 1] * 8)"), Ok(Interval::new(-1., 1.)));
+        assert_eq!(eval_str("sqrt([4, 9]) + abs([-1, 0.5])"), Ok(Interval::new(2., 4.)));
+        assert_eq!(eval_str("floor([0.5, 2.5]) + frac([1.25, 1.5])"), Ok(Interval::new(0.25, 2.5)));
+        assert_eq!(eval_str("max([0, 2], 1) + if(2 > 1, 0, [5, 6])"), Ok(Interval::new(1., 2.)));
+        // `3/10` lies strictly between the neighbours of `0.3`.
+        let rounded = eval_str("round(0.3, 1)").unwrap();
+        assert!(rounded.lo < 0.3 && 0.3 < rounded.hi);
+        assert!(eval_str("round(1.25±0.01, 1)").unwrap().contains(1.2));
+        assert_eq!(eval_str("round([0.5, 2.4])"), Ok(Interval::new(1., 2.)));
+    }
+
+    #[test]
+    fn interval_errors() {
+        assert_eq!(eval_str("1 / [-1, 1]"), Err(Error::new(ErrorKinds::DivisionByZero, vec![4..11])));
+        assert_eq!(eval_str("[5, 4]"), Err(Error::new(ErrorKinds::EmptyInterval, vec![0..6])));
+        assert_eq!(eval_str("if([1, 3] < 2, 0, 1)"), Err(Error::new(ErrorKinds::UncertainCondition, vec![3..13])));
+        assert_eq!(eval_str("sqrt([-1, 1])"), Err(Error::new(ErrorKinds::NegativeSqrt, vec![0..13])));
+        assert_eq!(
+            eval_str("asin(1±0.5)"),
+            Err(Error::new(ErrorKinds::NotInRange(-1., 1.), vec![0..12]))
+        );
+    }
+
+    #[test]
+    fn display_interval() {
+        assert_eq!(Interval::new(4.9, 5.1).to_string(), "[4.9, 5.1]");
+        assert_eq!(Interval::point(-2.).to_string(), "[-2, -2]");
+    }
+}
@@ -1,8 +1,160 @@
-#![allow(unused_imports)]
-#![allow(dead_code)]
+//! A parser and evaluator for mathematical expressions.
+//!
+//! ```
+//! use simple_eval::Value;
+//!
+//! assert_eq!(simple_eval::eval("2 * (3 + 4) ^ 2"), Ok(Value::Int(98)));
+//! assert_eq!(simple_eval::eval("7 / 2 > 3"), Ok(Value::Bool(true)));
+//! ```
 
+// Errors point to a list of spans, which often holds a single one.
+#![allow(clippy::single_range_in_vec_init)]
+
+mod context;
 mod maph_error;
 mod parse;
 mod eval;
 mod utils;
 mod ast;
+mod value;
+mod numeric;
+mod complex;
+mod interval;
+mod derive;
+mod simplify;
+mod export;
+mod compile;
+
+pub use crate::ast::consts::Const;
+pub use crate::compile::Program;
+pub use crate::complex::Complex;
+pub use crate::context::Context;
+pub use crate::derive::derive;
+pub use crate::ast::expr::Expr;
+pub use crate::ast::function::{CustomFunction, Function};
+pub use crate::ast::token::{operator::{Operator, UnaryOp}, Calculation, NumberLocale, Token, TokenKind, Tokenize};
+pub use crate::ast::tree::TreeNode;
+pub use crate::eval::Eval;
+pub use crate::interval::Interval;
+pub use crate::maph_error::{byte_to_char, char_to_byte, render_errors, Error, ErrorKinds, Span};
+pub use crate::numeric::{Irrational, Numeric, Rounding};
+#[cfg(feature = "decimal")]
+pub use bigdecimal::BigDecimal;
+#[cfg(feature = "rational")]
+pub use crate::numeric::ratio::Ratio;
+pub use crate::parse::Parse;
+pub use crate::value::{Value, ValueType};
+
+/// Parse `input` into an [`Expr`] which can be evaluated many times.
+pub fn parse(input: &str) -> Result<Expr, Vec<Error>> {
+    input.parse()
+}
+
+/// Parse `input` with the number format of `ctx`, resolving function names
+/// against the ones registered in it.
+pub fn parse_with(input: &str, ctx: &Context) -> Result<Expr, Vec<Error>> {
+    Expr::parse_with(input, ctx)
+}
+
+/// Parse and evaluate `input` in one call.
+pub fn eval(input: &str) -> Result<Value, Vec<Error>> {
+    parse(input)?.eval().map_err(|e| vec![e])
+}
+
+/// Parse and evaluate `input` with the variables and functions of `ctx`.
+pub fn eval_with(input: &str, ctx: &Context) -> Result<Value, Vec<Error>> {
+    parse_with(input, ctx)?.eval_with(ctx).map_err(|e| vec![e])
+}
+
+/// Parse `input` and evaluate it to the number type `N` with the variables
+/// and functions of `ctx`, see [`Expr::eval_numeric`].
+pub fn eval_numeric<N: Numeric>(input: &str, ctx: &Context) -> Result<N, Vec<Error>> {
+    parse_with(input, ctx)?.eval_numeric(ctx).map_err(|e| vec![e])
+}
+
+/// Parse `input` and evaluate it to a complex number with the variables and
+/// functions of `ctx`, see [`Expr::eval_complex`] and [`Context::set_complex`].
+pub fn eval_complex(input: &str, ctx: &Context) -> Result<Complex, Vec<Error>> {
+    parse_with(input, ctx)?.eval_complex(ctx).map_err(|e| vec![e])
+}
+
+/// Parse `input` and evaluate it to an interval with the variables and
+/// functions of `ctx`, see [`Expr::eval_interval`].
+pub fn eval_interval(input: &str, ctx: &Context) -> Result<Interval, Vec<Error>> {
+    parse_with(input, ctx)?.eval_interval(ctx).map_err(|e| vec![e])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eval_calculation() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(Value::Int(7)));
+    }
+
+    #[test]
+    fn eval_reports_errors() {
+        assert_eq!(eval("1 / (2 - 2)"), Err(vec![Error::new(ErrorKinds::DivisionByZero, vec![5..10])]));
+        assert_eq!(eval("2 + $"), Err(vec![Error::new(ErrorKinds::InvalideChar('$'), vec![4..5])]));
+    }
+
+    #[test]
+    fn eval_with_locale() {
+        let mut ctx = Context::new();
+        ctx.set_locale(NumberLocale::new(',', ';', Some('_')));
+        assert_eq!(eval_with("max(1_000,5; 2) * 2", &ctx), Ok(Value::Float(2001.)));
+    }
+
+    #[test]
+    fn eval_constants() {
+        assert_eq!(eval("tau / 2 - pi"), Ok(Value::Float(0.)));
+        assert_eq!(eval("phi^2 - phi"), Ok(Value::Float(1.)));
+        assert_eq!(eval("round(gamma * 1000)"), Ok(Value::Float(577.)));
+        assert!(eval("NaN + 1").unwrap().as_f64().unwrap().is_nan());
+        assert_eq!(parse("inf"), Ok(Expr::Const(Const::Inf(0..3))));
+        assert_eq!(parse("Nan"), Ok(Expr::Const(Const::NaN(0..3))));
+        assert_eq!(eval("2inf"), Ok(Value::Float(f64::INFINITY)));
+        let mut ctx = Context::new();
+        ctx.set_constant("c", 299_792_458.);
+        assert_eq!(eval_with("2c", &ctx), Ok(Value::Float(599_584_916.)));
+        ctx.set_constant("inf", 1.);
+        assert_eq!(eval_with("inf + 1", &ctx), Ok(Value::Float(2.)));
+    }
+
+    #[test]
+    fn eval_implicit_multiplication() {
+        let mut ctx = Context::new();
+        ctx.set_variable("r", 2.);
+        ctx.set_variable("x", 1.);
+        assert_eq!(eval_with("2pi r", &ctx), Ok(Value::Float(4. * std::f64::consts::PI)));
+        assert_eq!(eval_with("3pi", &ctx), eval("3 * pi"));
+        assert_eq!(eval_with("3(x+1)(x+2) - 2√4", &ctx), Ok(Value::Float(14.)));
+        ctx.set_implicit_multiplication(false);
+        assert!(eval_with("2pi r", &ctx).is_err());
+    }
+
+    #[test]
+    fn eval_complex_mode() {
+        let mut ctx = Context::new();
+        ctx.set_variable("i", 2);
+        assert_eq!(eval_with("3i", &ctx), Ok(Value::Int(6)));
+        ctx.set_complex(true);
+        assert_eq!(eval_complex("sqrt(-4) + 3j", &ctx).map(|z| z.to_string()), Ok("5i".to_owned()));
+        assert_eq!(eval_with("3i", &ctx), Err(vec![Error::new(ErrorKinds::Unsupported("i".to_owned()), vec![1..2])]));
+        assert_eq!(eval("re(2) + im(2) + conj(2) + arg(-2)"), Ok(Value::Float(4. + std::f64::consts::PI)));
+    }
+
+    #[test]
+    fn eval_uncertain() {
+        let mut ctx = Context::new();
+        ctx.set_variable("x", 2.5);
+        let n = eval_interval("2 * 5±0.1 + x", &ctx).unwrap();
+        assert!(n.contains(12.3) && n.contains(12.7) && n.width() < 0.4 + 1e-14);
+        assert_eq!(eval_interval("[0.5, x]^2", &ctx), Ok(Interval::new(0.25, 6.25)));
+        assert_eq!(
+            eval_with("5±0.1", &ctx),
+            Err(vec![Error::new(ErrorKinds::Unsupported("±".to_owned()), vec![0..6])])
+        );
+    }
+}
@@ -1,24 +1,104 @@
+use std::fmt;
+use std::ops::Range;
+
 use crate::ast::token::operator::Operator;
+use crate::value::ValueType;
+
+/// Range of the input covered by a token, an expression or an error, in bytes.
+pub type Span = Range<usize>;
+
+/// Index of the character at the byte offset `byte` of `input`.
+pub fn byte_to_char(input: &str, byte: usize) -> usize {
+    input.char_indices().take_while(|(i, _)| *i < byte).count()
+}
+
+/// Byte offset of the character at index `char_index` of `input`.
+pub fn char_to_byte(input: &str, char_index: usize) -> usize {
+    input.char_indices().nth(char_index).map_or(input.len(), |(i, _)| i)
+}
 
 #[derive(Debug, PartialEq)]
 pub struct Error {
     pub kind: ErrorKinds,
-    pub span: Vec<usize>,
+    pub span: Vec<Span>,
 }
 
 impl Error {
-    pub fn new(kind: ErrorKinds, span: Vec<usize>) -> Self {
+    pub fn new(kind: ErrorKinds, span: Vec<Span>) -> Self {
         Self { kind, span }
     }
 
-    #[inline]
-    pub fn add_span(&mut self, span: usize) {
-        for s in self.span.iter_mut() {
-            *s = span - *s
+    /// Render the error with the lines of `input` it points to, underlining
+    /// every offending span with `^`.
+    pub fn render(&self, input: &str) -> String {
+        let mut output = format!("error: {}\n", self.kind);
+        let mut span = self.span.clone();
+        span.sort_unstable_by_key(|s| (s.start, s.end));
+        span.dedup();
+
+        let lines = input.split('\n').collect::<Vec<_>>();
+        let gutter = lines.len().to_string().len();
+        let mut line_start = 0;
+        let mut first = true;
+        for (line_number, line) in lines.iter().enumerate() {
+            let line_len = line.len();
+            let line_end = if line_number + 1 == lines.len() { usize::MAX } else { line_start + line_len + 1 };
+            let columns = span
+                .iter()
+                .filter_map(|s| {
+                    // An empty span is still marked once.
+                    let end = s.end.max(s.start + 1);
+                    if s.start >= line_end || end <= line_start {
+                        return None;
+                    }
+                    let start = byte_to_char(line, s.start.max(line_start) - line_start);
+                    let end = byte_to_char(line, end.min(line_end) - line_start);
+                    Some(start..end.max(start + 1))
+                })
+                .collect::<Vec<_>>();
+            line_start += line_len + 1;
+            if columns.is_empty() {
+                continue;
+            }
+
+            if first {
+                output.push_str(&format!("{:gutter$} |\n", "", gutter = gutter));
+                first = false;
+            }
+            let mut markers = String::new();
+            for column in columns {
+                let marked = markers.chars().count();
+                if column.end <= marked {
+                    continue;
+                }
+                markers.push_str(&" ".repeat(column.start.saturating_sub(marked)));
+                markers.push_str(&"^".repeat(column.end - column.start.max(marked)));
+            }
+            output.push_str(&format!("{:>gutter$} | {}\n", line_number + 1, line.trim_end(), gutter = gutter));
+            output.push_str(&format!("{:gutter$} | {}\n", "", markers, gutter = gutter));
         }
+        output
     }
 }
 
+/// Render every error of `errors`, see [`Error::render`].
+pub fn render_errors(input: &str, errors: &[Error]) -> String {
+    errors.iter().map(|e| e.render(input)).collect::<Vec<_>>().join("\n")
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if !self.span.is_empty() {
+            let positions = self.span.iter().map(|s| format!("{}..{}", s.start, s.end)).collect::<Vec<_>>();
+            write!(f, " at {}", positions.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
 #[derive(Debug, PartialEq)]
 pub enum ErrorKinds {
     InvalideChar(char),
@@ -27,9 +107,120 @@ pub enum ErrorKinds {
     UnclosedBrack,
     UnexeptedBrack,
     UnexeptedOperator(Operator),
+    UnexeptedSeparator,
     DivisionByZero,
     ModuloByZero,
     NegativeSqrt,
     NotInRange(f64, f64),
-    UnknowFonction(String)
+    UnknowFonction(String),
+    MissingOperand,
+    MissingOperator,
+    UnknownVariable(String),
+    WrongArgumentCount { expected: usize, found: usize },
+    NotAnInteger(f64),
+    /// Operands of a binary operator with types it does not apply to.
+    TypeMismatch(ValueType, ValueType),
+    UnexpectedBoolean,
+    /// A complex number where only a real one is accepted.
+    NotReal,
+    /// A result an exact number type can not represent.
+    Inexact,
+    Unsupported(String),
+    UncertainCondition,
+    EmptyInterval,
+    /// A function or an operator without a derivative, such as `floor`.
+    NotDifferentiable(String),
+}
+
+impl fmt::Display for ErrorKinds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalideChar(c) => write!(f, "invalid character `{}`", c),
+            Self::MultipleComma => write!(f, "number with more than one decimal separator"),
+            Self::UnknowOperator(op) => write!(f, "unknown operator `{}`", op),
+            Self::UnclosedBrack => write!(f, "unclosed bracket"),
+            Self::UnexeptedBrack => write!(f, "unexpected closing bracket"),
+            Self::UnexeptedOperator(op) => write!(f, "unexpected operator `{}`", op),
+            Self::UnexeptedSeparator => write!(f, "unexpected argument separator"),
+            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::ModuloByZero => write!(f, "modulo by zero"),
+            Self::NegativeSqrt => write!(f, "square root of a negative number"),
+            Self::NotInRange(min, max) => write!(f, "argument not in range [{}, {}]", min, max),
+            Self::UnknowFonction(name) => write!(f, "unknown function `{}`", name),
+            Self::MissingOperand => write!(f, "missing operand"),
+            Self::MissingOperator => write!(f, "missing operator"),
+            Self::UnknownVariable(name) => write!(f, "unknown variable `{}`", name),
+            Self::NotAnInteger(n) => write!(f, "`{}` is not an integer", n),
+            Self::TypeMismatch(l, r) => write!(f, "mismatched operand types {} and {}", l, r),
+            Self::UnexpectedBoolean => write!(f, "expected a number, found a boolean"),
+            Self::NotReal => write!(f, "expected a real number, found a complex one"),
+            Self::Inexact => write!(f, "result can not be represented exactly"),
+            Self::Unsupported(op) => write!(f, "`{}` is not supported by this number type", op),
+            Self::UncertainCondition => write!(f, "condition only holds for a part of the interval"),
+            Self::EmptyInterval => write!(f, "lower bound greater than the upper bound"),
+            Self::NotDifferentiable(name) => write!(f, "`{}` has no derivative", name),
+            Self::WrongArgumentCount { expected, found } => write!(
+                f,
+                "expected {} argument{}, found {}",
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                found
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_error() {
+        let error = Error::new(ErrorKinds::MultipleComma, vec![6..7, 3..4]);
+        assert_eq!(error.to_string(), "number with more than one decimal separator at 6..7, 3..4");
+        let error = Error::new(ErrorKinds::WrongArgumentCount { expected: 2, found: 1 }, vec![]);
+        assert_eq!(error.to_string(), "expected 2 arguments, found 1");
+        let error = Error::new(ErrorKinds::TypeMismatch(ValueType::Bool, ValueType::Int), vec![0..6, 9..10]);
+        assert_eq!(error.to_string(), "mismatched operand types boolean and integer at 0..6, 9..10");
+    }
+
+    #[test]
+    fn render_error() {
+        let error = Error::new(ErrorKinds::MultipleComma, vec![6..7, 3..4]);
+        assert_eq!(
+            error.render("131.05. + 1"),
+            "error: number with more than one decimal separator\n  |\n1 | 131.05. + 1\n  |    ^  ^\n"
+        );
+        let error = Error::new(ErrorKinds::DivisionByZero, vec![4..11]);
+        assert_eq!(
+            error.render("1 / (2 - 2)"),
+            "error: division by zero\n  |\n1 | 1 / (2 - 2)\n  |     ^^^^^^^\n"
+        );
+        let error = Error::new(ErrorKinds::MissingOperand, vec![3..3]);
+        assert_eq!(error.render("2 +"), "error: missing operand\n  |\n1 | 2 +\n  |    ^\n");
+        let error = Error::new(ErrorKinds::UnknownVariable("y".to_owned()), vec![8..9]);
+        assert_eq!(error.render("√x × y"), "error: unknown variable `y`\n  |\n1 | √x × y\n  |      ^\n");
+    }
+
+    #[test]
+    fn convert_offsets() {
+        let input = "é + √2";
+        assert_eq!(byte_to_char(input, 5), 4);
+        assert_eq!(char_to_byte(input, 4), 5);
+        assert_eq!(char_to_byte(input, 5), 8);
+        assert_eq!(char_to_byte(input, 9), input.len());
+    }
+
+    #[test]
+    fn render_errors_on_multiple_lines() {
+        let errors = vec![
+            Error::new(ErrorKinds::InvalideChar('$'), vec![2..3]),
+            Error::new(ErrorKinds::UnclosedBrack, vec![4..5, 9..10]),
+        ];
+        assert_eq!(
+            render_errors("1 $\n(2 +\n(3", &errors),
+            "error: invalid character `$`\n  |\n1 | 1 $\n  |   ^\n\
+             \nerror: unclosed bracket\n  |\n2 | (2 +\n  | ^\n3 | (3\n  | ^\n"
+        );
+    }
 }
@@ -0,0 +1,486 @@
+use crate::ast::consts::Const;
+use crate::ast::expr::Expr;
+use crate::ast::function::Function;
+use crate::ast::token::operator::{Operator, UnaryOp};
+use crate::ast::tree::TreeNode;
+use crate::context::Context;
+use crate::eval::Eval;
+use crate::maph_error::{Error, ErrorKinds, Span};
+use crate::value::Value;
+
+#[cfg(feature = "decimal")]
+mod decimal;
+#[cfg(feature = "rational")]
+pub mod ratio;
+
+/// A number type an expression can be evaluated to, see [`Expr::eval_numeric`].
+///
+/// The `checked_*` operations give `None` when the result can not be represented
+/// exactly, in which case the evaluation falls back to `f64` if the backend is
+/// not exact or if [`Context::irrational`] is [`Irrational::Approximate`].
+pub trait Numeric: Sized + Clone + PartialOrd {
+    /// Whether the results of the backend are exact, or rounded to
+    /// [`Context::precision`] for a decimal.
+    const EXACT: bool;
+
+    fn from_i64(n: i64) -> Self;
+    /// The number written as the float `n`, `None` if out of range.
+    fn from_f64(n: f64) -> Option<Self>;
+    /// The number written in decimal as `s`, such as `0.25` or `1.5e-30`,
+    /// `None` if out of range.
+    fn from_decimal(s: &str) -> Option<Self>;
+    fn to_f64(&self) -> f64;
+
+    fn is_zero(&self) -> bool;
+    fn checked_add(&self, rhs: &Self) -> Option<Self>;
+    fn checked_sub(&self, rhs: &Self) -> Option<Self>;
+    fn checked_mul(&self, rhs: &Self) -> Option<Self>;
+    /// Division by a non-zero number.
+    fn checked_div(&self, rhs: &Self) -> Option<Self>;
+    fn checked_pow(&self, rhs: &Self) -> Option<Self>;
+    fn checked_neg(&self) -> Option<Self>;
+    /// Remainder of the division by a non-zero number, non-negative like `rem_euclid`.
+    fn checked_rem_euclid(&self, rhs: &Self) -> Option<Self>;
+    fn checked_sqrt(&self) -> Option<Self>;
+
+    fn abs(&self) -> Self;
+    fn floor(&self) -> Self;
+    fn ceil(&self) -> Self;
+    fn round(&self) -> Self;
+    fn trunc(&self) -> Self;
+    fn fract(&self) -> Self;
+
+    /// Round a result to `precision` significant digits, for a backend with a
+    /// limited precision. The number is kept as is by default.
+    fn with_precision(self, _precision: u64, _rounding: Rounding) -> Self {
+        self
+    }
+
+    /// Round to `digits` decimal places, or to tens, hundreds... when negative.
+    /// `None` on overflow.
+    fn round_to(&self, digits: i64, rounding: Rounding) -> Option<Self> {
+        let scale = Self::from_i64(10).checked_pow(&Self::from_i64(digits))?;
+        let scaled = self.checked_mul(&scale)?;
+        let (down, up) = if scaled < Self::from_i64(0) {
+            (scaled.ceil(), scaled.floor())
+        } else {
+            (scaled.floor(), scaled.ceil())
+        };
+        let rounded = match rounding {
+            Rounding::Up => up,
+            Rounding::Down => down,
+            Rounding::Ceiling => scaled.ceil(),
+            Rounding::Floor => scaled.floor(),
+            half => {
+                let distance = scaled.checked_sub(&down)?.abs();
+                let half_way = Self::from_i64(1).checked_div(&Self::from_i64(2))?;
+                if distance < half_way {
+                    down
+                } else if distance > half_way {
+                    up
+                } else {
+                    match half {
+                        Rounding::HalfUp => up,
+                        Rounding::HalfDown => down,
+                        _ if down.checked_div(&Self::from_i64(2))?.fract().is_zero() => down,
+                        _ => up,
+                    }
+                }
+            }
+        };
+        rounded.checked_div(&scale)
+    }
+}
+
+/// How to round a number, by `round` and by a decimal result which has more
+/// digits than [`Context::precision`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Rounding {
+    /// Away from zero.
+    Up,
+    /// Toward zero.
+    Down,
+    /// Toward positive infinity.
+    Ceiling,
+    /// Toward negative infinity.
+    Floor,
+    /// To the nearest, away from zero when half-way.
+    #[default]
+    HalfUp,
+    /// To the nearest, toward zero when half-way.
+    HalfDown,
+    /// To the nearest, to the even neighbour when half-way.
+    HalfEven,
+}
+
+/// What to do with a result which can not be represented exactly, such as
+/// `sin(1)` or `2^0.5`, when evaluating to an exact number type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Irrational {
+    /// Fail with [`ErrorKinds::Inexact`].
+    #[default]
+    Reject,
+    /// Compute the result with `f64` and convert it back.
+    Approximate,
+}
+
+impl Numeric for f64 {
+    const EXACT: bool = false;
+
+    fn from_i64(n: i64) -> Self {
+        n as f64
+    }
+
+    fn from_f64(n: f64) -> Option<Self> {
+        Some(n)
+    }
+
+    fn from_decimal(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == 0.
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        Some(self + rhs)
+    }
+
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        Some(self - rhs)
+    }
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        Some(self * rhs)
+    }
+
+    fn checked_div(&self, rhs: &Self) -> Option<Self> {
+        Some(self / rhs)
+    }
+
+    fn checked_pow(&self, rhs: &Self) -> Option<Self> {
+        Some(self.powf(*rhs))
+    }
+
+    fn checked_neg(&self) -> Option<Self> {
+        Some(-self)
+    }
+
+    fn checked_rem_euclid(&self, rhs: &Self) -> Option<Self> {
+        Some(self.rem_euclid(*rhs))
+    }
+
+    fn checked_sqrt(&self) -> Option<Self> {
+        Some(self.sqrt())
+    }
+
+    fn abs(&self) -> Self {
+        f64::abs(*self)
+    }
+
+    fn floor(&self) -> Self {
+        f64::floor(*self)
+    }
+
+    fn ceil(&self) -> Self {
+        f64::ceil(*self)
+    }
+
+    fn round(&self) -> Self {
+        f64::round(*self)
+    }
+
+    fn trunc(&self) -> Self {
+        f64::trunc(*self)
+    }
+
+    fn fract(&self) -> Self {
+        f64::fract(*self)
+    }
+}
+
+// Evaluator over a `Numeric` backend. Spans are only used for errors.
+pub(crate) fn eval<N: Numeric>(expr: &Expr, ctx: &Context) -> Result<N, Error> {
+    match expr {
+        Expr::Node(node) => eval_node(node, ctx),
+        // The digits of a long literal are exact, unlike its float.
+        Expr::Number(_, Some(digits), span) => {
+            N::from_decimal(digits).ok_or_else(|| Error::new(ErrorKinds::Inexact, vec![span.clone()]))
+        }
+        Expr::Number(n, None, span) => from_f64(*n, span),
+        Expr::Int(n, _) => Ok(N::from_i64(*n)),
+        Expr::Const(c) => eval_const(c, ctx),
+        Expr::Function(fnc) => eval_function(fnc, ctx),
+        Expr::Variable(name, span) => match ctx.get_variable(name) {
+            Some(Value::Int(n)) => Ok(N::from_i64(n)),
+            Some(Value::Float(n)) => from_f64(n, span),
+            Some(Value::Bool(_)) => Err(Error::new(ErrorKinds::UnexpectedBoolean, vec![span.clone()])),
+            None => Err(Error::new(ErrorKinds::UnknownVariable(name.clone()), vec![span.clone()])),
+        },
+        Expr::Unary(UnaryOp::Neg, expr, span) => {
+            let n = eval::<N>(expr, ctx)?;
+            exact(n.checked_neg(), || -n.to_f64(), ctx, span)
+        }
+        Expr::Unary(UnaryOp::Plus, expr, _) => eval(expr, ctx),
+        Expr::Unary(UnaryOp::Not, _, span) => Err(unsupported(UnaryOp::Not, span)),
+        Expr::Interval(_, _, span) => Err(unsupported("[lo, hi]", span)),
+    }
+}
+
+fn eval_node<N: Numeric>(node: &TreeNode, ctx: &Context) -> Result<N, Error> {
+    let l = eval::<N>(&node.l_expr, ctx)?;
+    let r = eval::<N>(&node.r_expr, ctx)?;
+    let span = &node.span;
+    let r_span = node.r_expr.span();
+    let (lf, rf) = (l.to_f64(), r.to_f64());
+    match node.op {
+        Operator::Add => exact(l.checked_add(&r), || lf + rf, ctx, span),
+        Operator::Sub => exact(l.checked_sub(&r), || lf - rf, ctx, span),
+        Operator::Mul => exact(l.checked_mul(&r), || lf * rf, ctx, span),
+        Operator::Div if r.is_zero() => Err(Error::new(ErrorKinds::DivisionByZero, vec![r_span.clone()])),
+        Operator::Div => exact(l.checked_div(&r), || lf / rf, ctx, span),
+        Operator::Pow => exact(l.checked_pow(&r), || lf.powf(rf), ctx, span),
+        Operator::Mod | Operator::FDiv if r.is_zero() => Err(Error::new(ErrorKinds::ModuloByZero, vec![r_span.clone()])),
+        Operator::Mod => exact(l.checked_rem_euclid(&r), || lf.rem_euclid(rf), ctx, span),
+        Operator::FDiv => exact(l.checked_div(&r).map(|n| n.trunc()), || (lf / rf).trunc(), ctx, span),
+        op => Err(unsupported(op, span)),
+    }
+}
+
+fn eval_const<N: Numeric>(c: &Const, ctx: &Context) -> Result<N, Error> {
+    match c {
+        // A custom constant is exact, as a literal.
+        Const::Custom(_, value, span) => from_f64(*value, span),
+        c => {
+            let value = c.eval_with(ctx)?.as_f64().unwrap_or(f64::NAN);
+            exact(None, || value, ctx, c.span())
+        }
+    }
+}
+
+fn eval_function<N: Numeric>(fnc: &Function, ctx: &Context) -> Result<N, Error> {
+    let span = fnc.span();
+    match fnc {
+        Function::Abs(expr, _) => Ok(eval::<N>(expr, ctx)?.abs()),
+        Function::Round(expr, digits, _) => {
+            let n = eval::<N>(expr, ctx)?;
+            let digits = match digits {
+                Some(digits) => digits.eval_with(ctx)?.to_integer(digits.span())?,
+                None => 0,
+            };
+            let rounded = n.round_to(digits, ctx.rounding());
+            exact(rounded, || n.to_f64().round_to(digits, ctx.rounding()).unwrap_or(f64::NAN), ctx, span)
+        }
+        Function::Floor(expr, _) => Ok(eval::<N>(expr, ctx)?.floor()),
+        Function::Ceil(expr, _) => Ok(eval::<N>(expr, ctx)?.ceil()),
+        Function::Trunc(expr, _) => Ok(eval::<N>(expr, ctx)?.trunc()),
+        Function::Frac(expr, _) => Ok(eval::<N>(expr, ctx)?.fract()),
+        Function::Re(expr, _) | Function::Conj(expr, _) => eval(expr, ctx),
+        Function::Im(expr, _) => eval::<N>(expr, ctx).map(|_| N::from_i64(0)),
+        Function::Arg(expr, _) => {
+            let n = eval::<N>(expr, ctx)?;
+            let zero = N::from_i64(0);
+            if n > zero || (n == zero && !n.to_f64().is_sign_negative()) {
+                return Ok(zero);
+            }
+            exact(None, || 0f64.atan2(n.to_f64()), ctx, span)
+        }
+        Function::Sqrt(expr, _) => {
+            let n = eval::<N>(expr, ctx)?;
+            if n < N::from_i64(0) {
+                return Err(Error::new(ErrorKinds::NegativeSqrt, vec![span.clone()]));
+            }
+            exact(n.checked_sqrt(), || n.to_f64().sqrt(), ctx, span)
+        }
+        Function::Max(args, _) | Function::Min(args, _) => {
+            let is_max = matches!(fnc, Function::Max(..));
+            let mut result = eval::<N>(&args[0], ctx)?;
+            for arg in &args[1..] {
+                let n = eval::<N>(arg, ctx)?;
+                // `NaN` is the only number unordered with itself, skip it like `f64::max`
+                let is_nan = result.partial_cmp(&result).is_none();
+                if is_nan || (is_max && n > result) || (!is_max && n < result) {
+                    result = n;
+                }
+            }
+            Ok(result)
+        }
+        Function::If(condition, then, otherwise, _) => {
+            let branch = if eval_condition::<N>(condition, ctx)? { then } else { otherwise };
+            eval(branch, ctx)
+        }
+        // A custom function computes with floats, so its result is only exact
+        // with `f64`, and is approximated as an irrational one otherwise.
+        Function::Custom(function, args, _) => {
+            let args = args
+                .iter()
+                .map(|arg| eval::<N>(arg, ctx).map(|n| n.to_f64()))
+                .collect::<Result<Vec<f64>, Error>>()?;
+            exact(None, || function.call(&args), ctx, span)
+        }
+        // Other functions are irrational.
+        fnc => {
+            let value = fnc.eval_with(ctx)?.as_f64().unwrap_or(f64::NAN);
+            exact(None, || value, ctx, span)
+        }
+    }
+}
+
+// Evaluate the condition of an `if`, comparing numbers with the backend.
+fn eval_condition<N: Numeric>(expr: &Expr, ctx: &Context) -> Result<bool, Error> {
+    match expr {
+        Expr::Node(node) if node.op == Operator::And => {
+            Ok(eval_condition::<N>(&node.l_expr, ctx)? && eval_condition::<N>(&node.r_expr, ctx)?)
+        }
+        Expr::Node(node) if node.op == Operator::Or => {
+            Ok(eval_condition::<N>(&node.l_expr, ctx)? || eval_condition::<N>(&node.r_expr, ctx)?)
+        }
+        Expr::Node(node) if node.op.is_boolean() => {
+            let l = eval::<N>(&node.l_expr, ctx)?;
+            let r = eval::<N>(&node.r_expr, ctx)?;
+            Ok(match node.op {
+                Operator::Eq => l == r,
+                Operator::Ne => l != r,
+                Operator::Lt => l < r,
+                Operator::Le => l <= r,
+                Operator::Gt => l > r,
+                _ => l >= r,
+            })
+        }
+        Expr::Unary(UnaryOp::Not, expr, _) => Ok(!eval_condition::<N>(expr, ctx)?),
+        Expr::Variable(name, _) => match ctx.get_variable(name) {
+            Some(Value::Bool(b)) => Ok(b),
+            _ => Ok(!eval::<N>(expr, ctx)?.is_zero()),
+        },
+        expr => Ok(!eval::<N>(expr, ctx)?.is_zero()),
+    }
+}
+
+// Take the exact result, or approximate it with `f64` when allowed.
+fn exact<N: Numeric>(result: Option<N>, approximate: impl FnOnce() -> f64, ctx: &Context, span: &Span) -> Result<N, Error> {
+    let n = match result {
+        Some(n) => n,
+        None if !N::EXACT || ctx.irrational() == Irrational::Approximate => from_f64(approximate(), span)?,
+        None => return Err(Error::new(ErrorKinds::Inexact, vec![span.clone()])),
+    };
+    Ok(n.with_precision(ctx.precision(), ctx.rounding()))
+}
+
+fn from_f64<N: Numeric>(n: f64, span: &Span) -> Result<N, Error> {
+    N::from_f64(n).ok_or_else(|| Error::new(ErrorKinds::Inexact, vec![span.clone()]))
+}
+
+fn unsupported(op: impl ToString, span: &Span) -> Error {
+    Error::new(ErrorKinds::Unsupported(op.to_string()), vec![span.clone()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval_str<N: Numeric>(input: &str, ctx: &Context) -> Result<N, Error> {
+        Expr::parse_with(input, ctx).unwrap().eval_numeric(ctx)
+    }
+
+    #[test]
+    fn eval_f64() {
+        let mut ctx = Context::new();
+        ctx.set_variable("x", 2);
+        assert_eq!(eval_str::<f64>("if(x >= 2, sin(0) + 2^0.5 * x, 0)", &ctx), Ok(2f64.sqrt() * 2.));
+        assert_eq!(
+            eval_str::<f64>("x & 1", &ctx),
+            Err(Error::new(ErrorKinds::Unsupported("&".to_owned()), vec![0..5]))
+        );
+        assert_eq!(eval_str::<f64>("5.5 % 0", &ctx), Err(Error::new(ErrorKinds::ModuloByZero, vec![6..7])));
+    }
+
+    #[test]
+    fn eval_custom_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let mut ctx = Context::new();
+        ctx.set_function("f", 1, move |args| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            args[0] - 3.
+        });
+        assert_eq!(eval_str::<f64>("arg(f(1))", &ctx), Ok(std::f64::consts::PI));
+        assert_eq!(eval_str::<f64>("arg(f(1 / 2) + 3)", &ctx), Ok(0.));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn eval_same_as_eval_with() {
+        let mut ctx = Context::new();
+        ctx.set_variable("x", f64::NAN);
+        ctx.set_variable("z", -0.);
+        for input in &[
+            "max(x, 1)",
+            "max(1, x)",
+            "min(x, 1)",
+            "min(1, x, 2)",
+            "max(x)",
+            "max(z, -1)",
+            "arg(x)",
+            "arg(z)",
+            "arg(0)",
+            "arg(-2)",
+        ] {
+            let numeric = eval_str::<f64>(input, &ctx).unwrap();
+            let value = Expr::parse_with(input, &ctx).unwrap().eval_with(&ctx).unwrap().as_f64().unwrap();
+            assert!(numeric == value || (numeric.is_nan() && value.is_nan()), "{}: {} != {}", input, numeric, value);
+            assert_eq!(numeric.is_sign_negative(), value.is_sign_negative(), "{}", input);
+        }
+    }
+
+    #[cfg(feature = "rational")]
+    #[test]
+    fn eval_rational() {
+        use super::ratio::Ratio;
+
+        let mut ctx = Context::new();
+        assert_eq!(eval_str("1/3*3", &ctx), Ok(Ratio::from_i64(1)));
+        assert_eq!(eval_str("0.1 + 0.2 - 0.3", &ctx), Ok(Ratio::from_i64(0)));
+        assert_eq!(eval_str("(2/3)^-2 + sqrt(9/4) + 7 % 2.5", &ctx), Ok(Ratio::new(23, 4).unwrap()));
+        assert_eq!(eval_str("if(1/3 + 1/3 + 1/3 == 1, 1, 0)", &ctx), Ok(Ratio::from_i64(1)));
+        assert_eq!(eval_str::<Ratio>("2 + sin(1)", &ctx), Err(Error::new(ErrorKinds::Inexact, vec![4..10])));
+        assert_eq!(eval_str::<Ratio>("2^0.5", &ctx), Err(Error::new(ErrorKinds::Inexact, vec![0..5])));
+        assert_eq!(eval_str::<Ratio>("1 / (1 - 1)", &ctx), Err(Error::new(ErrorKinds::DivisionByZero, vec![5..10])));
+        ctx.set_function("half", 1, |args| args[0] / 2.);
+        assert_eq!(eval_str::<Ratio>("half(3) + arg(-1)", &ctx), Err(Error::new(ErrorKinds::Inexact, vec![0..7])));
+        assert_eq!(eval_str("arg(1/3)", &ctx), Ok(Ratio::from_i64(0)));
+
+        ctx.set_irrational(Irrational::Approximate);
+        assert_eq!(eval_str("half(3)", &ctx), Ok(Ratio::new(3, 2).unwrap()));
+        assert_eq!(eval_str("2^0.5", &ctx), Ok(Ratio::from_f64(2f64.sqrt()).unwrap()));
+        assert_eq!(eval_str("round(2/3, 2)", &ctx), Ok(Ratio::new(67, 100).unwrap()));
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn eval_decimal() {
+        use bigdecimal::BigDecimal;
+        use std::str::FromStr;
+
+        let decimal = |n: &str| Ok(BigDecimal::from_str(n).unwrap());
+        let mut ctx = Context::new();
+        assert_eq!(eval_str("round(0.1 + 0.2, 2)", &ctx), decimal("0.3"));
+        assert_eq!(eval_str("1/3 * 3", &ctx), decimal("0.9999999999999999999999999999"));
+        assert_eq!(eval_str("floor(-2.5) + ceil(2.1) + trunc(-1.9) + sqrt(2.25)", &ctx), decimal("0.5"));
+        assert_eq!(eval_str::<BigDecimal>("sin(1)", &ctx), Err(Error::new(ErrorKinds::Inexact, vec![0..6])));
+        let digits = "0.1234567890123456789012345";
+        assert_eq!(eval_str(digits, &ctx), decimal(digits));
+        assert_eq!(eval_str("1e-400 * 1e400 + 2", &ctx), decimal("3"));
+
+        ctx.set_precision(4);
+        ctx.set_rounding(Rounding::Down);
+        assert_eq!(eval_str("2/3 + round(1.005, 2)", &ctx), decimal("1.666"));
+    }
+}
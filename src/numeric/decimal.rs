@@ -0,0 +1,149 @@
+use std::num::NonZeroU64;
+use std::str::FromStr;
+
+use bigdecimal::{BigDecimal, RoundingMode, Signed, ToPrimitive, Zero};
+
+use super::{Numeric, Rounding};
+
+impl From<Rounding> for RoundingMode {
+    fn from(rounding: Rounding) -> Self {
+        match rounding {
+            Rounding::Up => Self::Up,
+            Rounding::Down => Self::Down,
+            Rounding::Ceiling => Self::Ceiling,
+            Rounding::Floor => Self::Floor,
+            Rounding::HalfUp => Self::HalfUp,
+            Rounding::HalfDown => Self::HalfDown,
+            Rounding::HalfEven => Self::HalfEven,
+        }
+    }
+}
+
+/// Quotients and square roots are computed with 100 digits, then rounded to
+/// the precision of the context as every other result.
+impl Numeric for BigDecimal {
+    const EXACT: bool = true;
+
+    fn from_i64(n: i64) -> Self {
+        Self::from(n)
+    }
+
+    /// The float as written in its shortest decimal form, so `0.1` is `0.1`.
+    fn from_f64(n: f64) -> Option<Self> {
+        if !n.is_finite() {
+            return None;
+        }
+        Self::from_str(&n.to_string()).ok()
+    }
+
+    fn from_decimal(s: &str) -> Option<Self> {
+        Self::from_str(s).ok()
+    }
+
+    fn to_f64(&self) -> f64 {
+        ToPrimitive::to_f64(self).unwrap_or(f64::NAN)
+    }
+
+    fn is_zero(&self) -> bool {
+        Zero::is_zero(self)
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        Some(self + rhs)
+    }
+
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        Some(self - rhs)
+    }
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        Some(self * rhs)
+    }
+
+    fn checked_div(&self, rhs: &Self) -> Option<Self> {
+        Some(self / rhs)
+    }
+
+    /// Exact for an integer exponent only.
+    fn checked_pow(&self, rhs: &Self) -> Option<Self> {
+        if !rhs.is_integer() {
+            return None;
+        }
+        Some(self.powi(rhs.to_i64()?))
+    }
+
+    fn checked_neg(&self) -> Option<Self> {
+        Some(-self)
+    }
+
+    fn checked_rem_euclid(&self, rhs: &Self) -> Option<Self> {
+        let rem = self % rhs;
+        if rem.is_negative() { Some(rem + rhs.abs()) } else { Some(rem) }
+    }
+
+    fn checked_sqrt(&self) -> Option<Self> {
+        self.sqrt()
+    }
+
+    fn abs(&self) -> Self {
+        Signed::abs(self)
+    }
+
+    fn floor(&self) -> Self {
+        self.with_scale_round(0, RoundingMode::Floor)
+    }
+
+    fn ceil(&self) -> Self {
+        self.with_scale_round(0, RoundingMode::Ceiling)
+    }
+
+    fn round(&self) -> Self {
+        self.with_scale_round(0, RoundingMode::HalfUp)
+    }
+
+    fn trunc(&self) -> Self {
+        self.with_scale_round(0, RoundingMode::Down)
+    }
+
+    fn fract(&self) -> Self {
+        self - self.trunc()
+    }
+
+    fn with_precision(self, precision: u64, rounding: Rounding) -> Self {
+        let precision = NonZeroU64::new(precision).unwrap_or(NonZeroU64::MIN);
+        self.with_precision_round(precision, rounding.into()).normalized()
+    }
+
+    /// Exact, without going through a multiplication by a power of ten.
+    fn round_to(&self, digits: i64, rounding: Rounding) -> Option<Self> {
+        Some(self.with_scale_round(digits, rounding.into()).normalized())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(n: &str) -> BigDecimal {
+        BigDecimal::from_str(n).unwrap()
+    }
+
+    #[test]
+    fn decimal_arithmetic() {
+        assert_eq!(BigDecimal::from_f64(0.1).unwrap().checked_add(&decimal("0.2")), Some(decimal("0.3")));
+        assert_eq!(decimal("1.5").checked_pow(&BigDecimal::from_i64(3)), Some(decimal("3.375")));
+        assert_eq!(decimal("2").checked_pow(&decimal("0.5")), None);
+        assert_eq!(decimal("-7.5").checked_rem_euclid(&decimal("2")), Some(decimal("0.5")));
+        assert_eq!(decimal("-7.5").fract(), decimal("-0.5"));
+    }
+
+    #[test]
+    fn decimal_rounding() {
+        let third = decimal("1").checked_div(&decimal("3")).unwrap();
+        assert_eq!(third.with_precision(5, Rounding::HalfUp), decimal("0.33333"));
+        assert_eq!(decimal("2.5").round_to(0, Rounding::HalfEven), Some(decimal("2")));
+        assert_eq!(decimal("-2.345").round_to(2, Rounding::HalfUp), Some(decimal("-2.35")));
+        assert_eq!(decimal("-2.345").round_to(2, Rounding::Floor), Some(decimal("-2.35")));
+        assert_eq!(decimal("1250").round_to(-2, Rounding::HalfDown), Some(decimal("1200")));
+    }
+}
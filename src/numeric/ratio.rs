@@ -0,0 +1,255 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+
+use super::Numeric;
+
+/// An exact rational number, a fraction of `i128` in lowest terms with a
+/// positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ratio {
+    numer: i128,
+    denom: i128,
+}
+
+impl Ratio {
+    /// `numer / denom` in lowest terms, `None` if `denom` is zero or on overflow.
+    pub fn new(numer: i128, denom: i128) -> Option<Self> {
+        if denom == 0 {
+            return None;
+        }
+        let gcd = gcd(numer, denom);
+        let (mut numer, mut denom) = (numer / gcd, denom / gcd);
+        if denom < 0 {
+            numer = numer.checked_neg()?;
+            denom = denom.checked_neg()?;
+        }
+        Some(Self { numer, denom })
+    }
+
+    #[inline]
+    pub fn from_integer(n: i128) -> Self {
+        Self { numer: n, denom: 1 }
+    }
+
+    #[inline]
+    pub fn numer(&self) -> i128 {
+        self.numer
+    }
+
+    #[inline]
+    pub fn denom(&self) -> i128 {
+        self.denom
+    }
+
+    #[inline]
+    pub fn is_integer(&self) -> bool {
+        self.denom == 1
+    }
+
+    fn recip(&self) -> Option<Self> {
+        Self::new(self.denom, self.numer)
+    }
+}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    // `gcd(0, 0)` would be `0`, only `denom` being non-zero matters.
+    if a == 0 { 1 } else { a.abs() }
+}
+
+// Square root of a perfect square.
+fn exact_sqrt(n: i128) -> Option<i128> {
+    let root = (n as f64).sqrt() as i128;
+    (root.saturating_sub(1)..=root.saturating_add(1)).find(|r| r.checked_mul(*r) == Some(n))
+}
+
+impl Numeric for Ratio {
+    const EXACT: bool = true;
+
+    fn from_i64(n: i64) -> Self {
+        Self::from_integer(n as i128)
+    }
+
+    /// The float as written in its shortest decimal form, so `0.1` is `1/10`.
+    fn from_f64(n: f64) -> Option<Self> {
+        if !n.is_finite() {
+            return None;
+        }
+        Self::from_decimal(&n.to_string())
+    }
+
+    fn from_decimal(s: &str) -> Option<Self> {
+        let (mantissa, exp) = match s.split_once(['e', 'E']) {
+            Some((mantissa, exp)) => (mantissa, exp.parse::<i32>().ok()?),
+            None => (s, 0),
+        };
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let numer = format!("{}{}", int, frac).parse::<i128>().ok()?;
+        let exp = exp.checked_sub(i32::try_from(frac.len()).ok()?)?;
+        let scale = 10i128.checked_pow(exp.unsigned_abs())?;
+        if exp >= 0 {
+            Self::new(numer.checked_mul(scale)?, 1)
+        } else {
+            Self::new(numer, scale)
+        }
+    }
+
+    fn to_f64(&self) -> f64 {
+        self.numer as f64 / self.denom as f64
+    }
+
+    fn is_zero(&self) -> bool {
+        self.numer == 0
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        let gcd = gcd(self.denom, rhs.denom);
+        let numer = self
+            .numer
+            .checked_mul(rhs.denom / gcd)?
+            .checked_add(rhs.numer.checked_mul(self.denom / gcd)?)?;
+        Self::new(numer, self.denom.checked_mul(rhs.denom / gcd)?)
+    }
+
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        self.checked_add(&rhs.checked_neg()?)
+    }
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        // Reduce crosswise first to delay overflows.
+        let (a, b) = (gcd(self.numer, rhs.denom), gcd(rhs.numer, self.denom));
+        Self::new(
+            (self.numer / a).checked_mul(rhs.numer / b)?,
+            (self.denom / b).checked_mul(rhs.denom / a)?,
+        )
+    }
+
+    fn checked_div(&self, rhs: &Self) -> Option<Self> {
+        self.checked_mul(&rhs.recip()?)
+    }
+
+    /// Exact for an integer exponent only.
+    fn checked_pow(&self, rhs: &Self) -> Option<Self> {
+        if !rhs.is_integer() {
+            return None;
+        }
+        let exp = u32::try_from(rhs.numer.checked_abs()?).ok()?;
+        let pow = Self::new(self.numer.checked_pow(exp)?, self.denom.checked_pow(exp)?)?;
+        if rhs.numer < 0 { pow.recip() } else { Some(pow) }
+    }
+
+    fn checked_neg(&self) -> Option<Self> {
+        Some(Self { numer: self.numer.checked_neg()?, denom: self.denom })
+    }
+
+    fn checked_rem_euclid(&self, rhs: &Self) -> Option<Self> {
+        let quotient = self.checked_div(rhs)?.floor();
+        let rem = self.checked_sub(&rhs.checked_mul(&quotient)?)?;
+        if rem.numer < 0 { rem.checked_add(&rhs.abs()) } else { Some(rem) }
+    }
+
+    /// Exact for the square of a rational only.
+    fn checked_sqrt(&self) -> Option<Self> {
+        Self::new(exact_sqrt(self.numer)?, exact_sqrt(self.denom)?)
+    }
+
+    fn abs(&self) -> Self {
+        Self { numer: self.numer.abs(), denom: self.denom }
+    }
+
+    fn floor(&self) -> Self {
+        Self::from_integer(self.numer.div_euclid(self.denom))
+    }
+
+    fn ceil(&self) -> Self {
+        Self::from_integer(-(-self.numer).div_euclid(self.denom))
+    }
+
+    /// Half-way cases are rounded away from zero, as `f64::round`.
+    fn round(&self) -> Self {
+        let half = Self { numer: self.numer.signum(), denom: 2 };
+        self.checked_add(&half)
+            .map_or_else(|| Self::from_integer(self.to_f64().round() as i128), |n| n.trunc())
+    }
+
+    fn trunc(&self) -> Self {
+        Self::from_integer(self.numer / self.denom)
+    }
+
+    fn fract(&self) -> Self {
+        Self { numer: self.numer % self.denom, denom: self.denom }
+    }
+}
+
+impl PartialOrd for Ratio {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ratio {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.numer.checked_mul(other.denom), other.numer.checked_mul(self.denom)) {
+            (Some(l), Some(r)) => l.cmp(&r),
+            _ => self.to_f64().total_cmp(&other.to_f64()),
+        }
+    }
+}
+
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ratio(numer: i128, denom: i128) -> Ratio {
+        Ratio::new(numer, denom).unwrap()
+    }
+
+    #[test]
+    fn ratio_arithmetic() {
+        assert_eq!(ratio(2, -4), ratio(-1, 2));
+        assert_eq!(ratio(1, 3).checked_add(&ratio(1, 6)), Some(ratio(1, 2)));
+        assert_eq!(ratio(1, 3).checked_mul(&Ratio::from_i64(3)), Some(Ratio::from_i64(1)));
+        assert_eq!(ratio(2, 3).checked_pow(&Ratio::from_i64(-2)), Some(ratio(9, 4)));
+        assert_eq!(ratio(2, 3).checked_pow(&ratio(1, 2)), None);
+        assert_eq!(ratio(4, 9).checked_sqrt(), Some(ratio(2, 3)));
+        assert_eq!(ratio(2, 1).checked_sqrt(), None);
+        assert_eq!(ratio(-7, 2).checked_rem_euclid(&ratio(3, 1)), Some(ratio(5, 2)));
+        assert_eq!(Ratio::from_integer(i128::MAX).checked_add(&Ratio::from_i64(1)), None);
+    }
+
+    #[test]
+    fn ratio_rounding() {
+        assert_eq!(ratio(-7, 2).floor(), Ratio::from_i64(-4));
+        assert_eq!(ratio(-7, 2).ceil(), Ratio::from_i64(-3));
+        assert_eq!(ratio(-7, 2).round(), Ratio::from_i64(-4));
+        assert_eq!(ratio(7, 3).round(), Ratio::from_i64(2));
+        assert_eq!(ratio(-7, 2).trunc(), Ratio::from_i64(-3));
+        assert_eq!(ratio(-7, 2).fract(), ratio(-1, 2));
+    }
+
+    #[test]
+    fn ratio_from_f64() {
+        assert_eq!(Ratio::from_f64(0.1), Some(ratio(1, 10)));
+        assert_eq!(Ratio::from_f64(-2.5), Some(ratio(-5, 2)));
+        assert_eq!(Ratio::from_f64(f64::NAN), None);
+        assert_eq!(Ratio::from_decimal("1.2345678901234567891e-3"), Some(ratio(12345678901234567891, 10i128.pow(22))));
+        assert_eq!(Ratio::from_decimal("2.5e3"), Some(Ratio::from_i64(2500)));
+        assert_eq!(ratio(-5, 2).to_string(), "-5/2");
+        assert!(ratio(1, 3) < ratio(1, 2));
+    }
+}
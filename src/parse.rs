@@ -1,7 +1,10 @@
-
 use std::collections::VecDeque;
+use std::iter::Peekable;
+use std::vec::IntoIter;
 
+use crate::context::Context;
 use crate::ast::{
+    consts::Const,
     expr::Expr,
     function::{
         Function,
@@ -9,28 +12,26 @@ use crate::ast::{
     },
     tree::TreeNode
 };
-use crate::token;
 
 use crate::ast::token::{
     Calculation,
+    Token,
     TokenKind,
-    Tokenize,
-    operator::Operator
+    operator::{Operator, UnaryOp}
 };
-use crate::maph_error::{Error, ErrorKinds};
+use crate::maph_error::{Error, ErrorKinds, Span};
 
 pub trait Parse {
     type Err;
     type ItSelf;
     type Output;
 
-    fn pre_parse(self) -> Result<Self::ItSelf, Self::Err>;
+    fn pre_parse(self, ctx: &Context) -> Result<Self::ItSelf, Self::Err>;
     fn verify_scoping(&self) -> Result<(), Self::Err>;
     fn verify_operator(&self) -> Result<(), Self::Err>;
-    fn concat_minus_and_number(&mut self);
-    fn verify_fonction_name(&self) -> Result<(), Self::Err>;
+    fn verify_fonction_name(&self, ctx: &Context) -> Result<(), Self::Err>;
 
-    fn parse(self) -> Result<Self::Output, Self::Err>;
+    fn parse(self, ctx: &Context) -> Result<Self::Output, Self::Err>;
 }
 
 impl Parse for Calculation {
@@ -38,25 +39,20 @@ impl Parse for Calculation {
     type ItSelf = Self;
     type Output = Expr;
 
-    fn pre_parse(mut self) -> Result<Self::ItSelf, Self::Err> {
+    fn pre_parse(self, ctx: &Context) -> Result<Self::ItSelf, Self::Err> {
         
         let mut errors = vec![];
         
-        match self.verify_scoping() {
-            Err(mut e) => errors.append(&mut e),
-            Ok(_) => {}
+        if let Err(mut e) = self.verify_scoping() {
+            errors.append(&mut e)
         }
 
-        self.concat_minus_and_number();
-
-        match self.verify_operator() {
-            Err(mut e) => errors.append(&mut e),
-            Ok(_) => {}
+        if let Err(mut e) = self.verify_operator() {
+            errors.append(&mut e)
         }
 
-        match self.verify_fonction_name() {
-            Err(mut e) => errors.append(&mut e),
-            Ok(_) => {}
+        if let Err(mut e) = self.verify_fonction_name(ctx) {
+            errors.append(&mut e)
         }
 
         if !errors.is_empty() {
@@ -67,20 +63,23 @@ impl Parse for Calculation {
     }
 
     fn verify_scoping(&self) -> Result<(), Self::Err> {
+        // Opening brackets, `true` for the square ones of an interval.
         let mut deep = VecDeque::new();
         for tk in self.iter() {
             match tk.token_kind {
-                TokenKind::LBrack => deep.push_back(tk.span),
-                TokenKind::RBrack => {
-                    if let None = deep.pop_back() {
-                        return Err(vec![Error::new(ErrorKinds::UnexeptedBrack, vec![tk.span])])
+                TokenKind::LBrack => deep.push_back((false, tk.span.clone())),
+                TokenKind::LSquare => deep.push_back((true, tk.span.clone())),
+                TokenKind::RBrack | TokenKind::RSquare => {
+                    let square = tk.token_kind == TokenKind::RSquare;
+                    if !matches!(deep.pop_back(), Some((opening, _)) if opening == square) {
+                        return Err(vec![Error::new(ErrorKinds::UnexeptedBrack, vec![tk.span.clone()])]);
                     }
-                },
+                }
                 _ => {}
             }
         }
-        if deep.len() > 0 {
-            Err(vec![Error::new(ErrorKinds::UnclosedBrack, deep.drain(0..deep.len()).collect())])
+        if !deep.is_empty() {
+            Err(vec![Error::new(ErrorKinds::UnclosedBrack, deep.drain(0..deep.len()).map(|(_, span)| span).collect())])
         } else {
             Ok(())
         }
@@ -88,21 +87,23 @@ impl Parse for Calculation {
 
     fn verify_operator(&self) -> Result<(), Self::Err> {
         let mut errors = vec![];
-        let mut pre_operator = (false, 0);
+        let mut pre_operator = (false, 0..0);
         let mut error_raise = false;
         for tk in self.iter() {
             match tk.token_kind {
                 TokenKind::Op(op) => {
-                    if !error_raise && pre_operator.0 {
+                    // A `+` or `-` following an operator is a sign.
+                    let is_sign = matches!(op, Operator::Add | Operator::Sub);
+                    if !error_raise && pre_operator.0 && !is_sign {
                         error_raise = true;
-                        errors.push(Error::new(ErrorKinds::UnexeptedOperator(op.clone()), vec![pre_operator.1]))
+                        errors.push(Error::new(ErrorKinds::UnexeptedOperator(op), vec![pre_operator.1.clone()]))
                     }
                     if !pre_operator.0 {
-                        pre_operator = (true, tk.span)
+                        pre_operator = (true, tk.span.clone())
                     }
                 }
                 _ => {
-                    pre_operator = (false, 0);
+                    pre_operator = (false, 0..0);
                     error_raise = false;
                 },
             }
@@ -114,30 +115,15 @@ impl Parse for Calculation {
         }
     }
 
-    fn concat_minus_and_number(&mut self) {
-        let mut sub_index = vec![];
-        let mut number = None;
-        for (idx, tk) in self.iter_mut().enumerate().rev() {
-            match tk.token_kind {
-                TokenKind::Number(ref mut x) => number = Some(x),
-                TokenKind::Op(Operator::Sub) => if let Some(ref mut x) = number {
-                    **x = - **x;
-                    sub_index.push(idx)
-                },
-                _ => number = None
-            }
-        }
-        for i in sub_index.into_iter() {
-            self.remove(i);
-        }
-    }
-
-    fn verify_fonction_name(&self) -> Result<(), Self::Err> {
+    fn verify_fonction_name(&self, ctx: &Context) -> Result<(), Self::Err> {
         let mut errors = vec![];
-        for tk in self {
-            match &tk.token_kind {
-                TokenKind::Ident(name) if !FUNCTION_NAME.contains(&name.as_str()) || FUNCTION_NAME.contains(&name.to_lowercase().as_str()) => {
-                    errors.push(Error::new(ErrorKinds::UnknowFonction(name.clone()), vec![tk.span]))
+        for (idx, tk) in self.iter().enumerate() {
+            // Any identifier not followed by a bracket is a constant or a variable.
+            match (&tk.token_kind, self.get(idx + 1).map(|next| &next.token_kind)) {
+                (TokenKind::Ident(name), Some(TokenKind::LBrack))
+                    if ctx.get_function(name).is_none() && !FUNCTION_NAME.contains(&name.to_lowercase().as_str()) =>
+                {
+                    errors.push(Error::new(ErrorKinds::UnknowFonction(name.clone()), vec![tk.span.clone()]))
                 }
                 _ => {}
             }
@@ -149,27 +135,546 @@ impl Parse for Calculation {
         }
     }
 
-    fn parse(self) -> Result<Self::Output, Self::Err> {
-        todo!("Parse");
+    fn parse(self, ctx: &Context) -> Result<Self::Output, Self::Err> {
+        let mut parser = Parser::new(self, ctx);
+        let (expr, _) = parser.expression(0).map_err(|e| vec![e])?;
+        match parser.next() {
+            Some(Token { token_kind: TokenKind::Separator, span }) => {
+                Err(vec![Error::new(ErrorKinds::UnexeptedSeparator, vec![span])])
+            }
+            Some(tk) => Err(vec![Error::new(ErrorKinds::MissingOperator, vec![tk.span.clone()])]),
+            None => Ok(expr),
+        }
     }
 }
 
+/// Precedence of implicit multiplication, between `±` and `^`.
+const IMPLICIT_MUL_PRECEDENCE: u8 = 11;
+
+// Precedence climbing parser over a pre-parsed calculation.
+struct Parser<'a> {
+    tokens: Peekable<IntoIter<Token>>,
+    last_end: usize,
+    ctx: &'a Context,
+}
+
+impl<'a> Parser<'a> {
+    fn new(calculation: Calculation, ctx: &'a Context) -> Self {
+        Self {
+            tokens: calculation.into_iter().peekable(),
+            last_end: 0,
+            ctx,
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let tk = self.tokens.next();
+        if let Some(tk) = &tk {
+            self.last_end = tk.span.end;
+        }
+        tk
+    }
+
+    #[inline]
+    fn peek_kind(&mut self) -> Option<&TokenKind> {
+        self.tokens.peek().map(|tk| &tk.token_kind)
+    }
+
+    // Parse operators binding at least as tight as `min_precedence`.
+    // The returned span includes the brackets around the expression if any.
+    fn expression(&mut self, min_precedence: u8) -> Result<(Expr, Span), Error> {
+        let (mut l_expr, mut span) = self.operand()?;
+        loop {
+            let implicit = self.ctx.implicit_multiplication();
+            let (op, precedence) = match self.peek_kind() {
+                Some(&TokenKind::Op(op)) => (op, op.precedence()),
+                Some(TokenKind::LBrack | TokenKind::LSquare | TokenKind::Ident(_) | TokenKind::Root) if implicit => {
+                    (Operator::Mul, IMPLICIT_MUL_PRECEDENCE)
+                }
+                _ => break,
+            };
+            if precedence < min_precedence {
+                break;
+            }
+            if precedence != IMPLICIT_MUL_PRECEDENCE {
+                self.next();
+            }
+            let next_precedence = if op.is_right_associative() { precedence } else { precedence + 1 };
+            let (r_expr, r_span) = self.expression(next_precedence)?;
+            span = span.start..r_span.end;
+            l_expr = Expr::Node(Box::new(TreeNode::new(l_expr, op, r_expr, span.clone())));
+        }
+        Ok((l_expr, span))
+    }
+
+    fn operand(&mut self) -> Result<(Expr, Span), Error> {
+        match self.next() {
+            Some(Token { token_kind: TokenKind::Number(n, digits), span }) => {
+                Ok((Expr::Number(n, digits, span.clone()), span))
+            }
+            Some(Token { token_kind: TokenKind::Int(n), span }) => Ok((Expr::Int(n, span.clone()), span)),
+            Some(Token { token_kind: TokenKind::LBrack, span }) => {
+                let (expr, _) = self.expression(0)?;
+                let rbrack = self.close_bracket(span.clone())?;
+                Ok((expr, span.start..rbrack.end))
+            }
+            Some(Token { token_kind: TokenKind::LSquare, span }) => {
+                let (lo, _) = self.expression(0)?;
+                match self.next() {
+                    Some(Token { token_kind: TokenKind::Separator, .. }) => {}
+                    Some(tk) => return Err(Error::new(ErrorKinds::MissingOperator, vec![tk.span])),
+                    None => return Err(Error::new(ErrorKinds::UnclosedBrack, vec![span])),
+                }
+                let (hi, _) = self.expression(0)?;
+                let rsquare = self.close_bracket(span.clone())?;
+                let span = span.start..rsquare.end;
+                Ok((Expr::Interval(Box::new(lo), Box::new(hi), span.clone()), span))
+            }
+            Some(Token { token_kind: TokenKind::Ident(name), span }) => {
+                if let Some(TokenKind::LBrack) = self.peek_kind() {
+                    let (args, rbrack) = self.arguments()?;
+                    let span = span.start..rbrack.end;
+                    let function = self.function(name, args, span.clone())?;
+                    Ok((Expr::Function(Box::new(function)), span))
+                } else {
+                    let expr = Const::with_context(&name, span.clone(), self.ctx)
+                        .map(Expr::Const)
+                        .unwrap_or_else(|| Expr::Variable(name, span.clone()));
+                    Ok((expr, span))
+                }
+            }
+            Some(Token { token_kind: TokenKind::Root, span }) => {
+                let (arg, arg_span) = self.operand()?;
+                let span = span.start..arg_span.end;
+                Ok((Expr::Function(Box::new(Function::Sqrt(arg, span.clone()))), span))
+            }
+            Some(Token { token_kind: TokenKind::Op(op), span }) if matches!(op, Operator::Add | Operator::Sub) => {
+                let op = if op == Operator::Sub { UnaryOp::Neg } else { UnaryOp::Plus };
+                self.unary(op, span)
+            }
+            Some(Token { token_kind: TokenKind::Not, span }) => self.unary(UnaryOp::Not, span),
+            Some(Token { token_kind: TokenKind::Op(op), span }) => {
+                Err(Error::new(ErrorKinds::UnexeptedOperator(op), vec![span]))
+            }
+            Some(Token { token_kind: TokenKind::RBrack | TokenKind::RSquare | TokenKind::Separator, span }) => {
+                Err(Error::new(ErrorKinds::MissingOperand, vec![span]))
+            }
+            None => Err(Error::new(ErrorKinds::MissingOperand, vec![self.last_end..self.last_end])),
+        }
+    }
+
+    // Parse the operand of the prefix operator `op` found at `span`.
+    fn unary(&mut self, op: UnaryOp, span: Span) -> Result<(Expr, Span), Error> {
+        // A prefix operator binds tighter than any operator but `^`, so `-2^2` is `-(2^2)`.
+        let (expr, expr_span) = self.expression(Operator::Pow.precedence())?;
+        let span = span.start..expr_span.end;
+        Ok((Expr::Unary(op, Box::new(expr), span.clone()), span))
+    }
+
+    // Parse a bracketed, comma separated argument list.
+    fn arguments(&mut self) -> Result<(Vec<Expr>, Span), Error> {
+        let lbrack = self.next().unwrap().span;
+        let mut args = vec![];
+        if let Some(TokenKind::RBrack) = self.peek_kind() {
+            return Ok((args, self.next().unwrap().span));
+        }
+        loop {
+            args.push(self.expression(0)?.0);
+            match self.next() {
+                Some(Token { token_kind: TokenKind::Separator, .. }) => {}
+                Some(Token { token_kind: TokenKind::RBrack, span }) => return Ok((args, span)),
+                Some(tk) => return Err(Error::new(ErrorKinds::MissingOperator, vec![tk.span])),
+                None => return Err(Error::new(ErrorKinds::UnclosedBrack, vec![lbrack])),
+            }
+        }
+    }
+
+    fn function(&self, name: String, args: Vec<Expr>, span: Span) -> Result<Function, Error> {
+        match self.ctx.get_function(&name) {
+            Some(custom) if custom.arity != args.len() => Err(Error::new(
+                ErrorKinds::WrongArgumentCount { expected: custom.arity, found: args.len() },
+                vec![span],
+            )),
+            Some(custom) => Ok(Function::Custom(custom.clone(), args, span)),
+            None => Function::new(&name, args, span.clone()).map_err(|kind| Error::new(kind, vec![span])),
+        }
+    }
+
+    fn close_bracket(&mut self, lbrack: Span) -> Result<Span, Error> {
+        match self.next() {
+            Some(Token { token_kind: TokenKind::RBrack | TokenKind::RSquare, span }) => Ok(span),
+            Some(Token { token_kind: TokenKind::Separator, span }) => {
+                Err(Error::new(ErrorKinds::UnexeptedSeparator, vec![span]))
+            }
+            Some(tk) => Err(Error::new(ErrorKinds::MissingOperator, vec![tk.span])),
+            None => Err(Error::new(ErrorKinds::UnclosedBrack, vec![lbrack])),
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::token::{token, Tokenize};
+
+    fn parse_str(s: &str) -> Result<Expr, Vec<Error>> {
+        let ctx = Context::default();
+        s.tokenize()?.pre_parse(&ctx)?.parse(&ctx)
+    }
+
+    fn node(l_expr: Expr, op: Operator, r_expr: Expr, span: Span) -> Expr {
+        Expr::Node(Box::new(TreeNode::new(l_expr, op, r_expr, span)))
+    }
 
     #[test]
     fn pre_parse_add() {
         let calculation = "2 + 3".tokenize().unwrap();
-        let parsable = calculation.pre_parse().unwrap();
+        let parsable = calculation.pre_parse(&Context::default()).unwrap();
         assert_eq!(
             parsable,
             vec![
-                token!(Number(2.), 0),
-                token!(Op::Add, 2),
-                token!(Number(3.), 4)
+                token!(Int(2), 0..1),
+                token!(Op::Add, 2..3),
+                token!(Int(3), 4..5)
             ]
         );
     }
+
+    #[test]
+    fn pre_parse_unexepted_operator() {
+        let calculation = "2 * -3 + * 4".tokenize().unwrap();
+        assert_eq!(
+            calculation.pre_parse(&Context::default()),
+            Err(vec![Error::new(ErrorKinds::UnexeptedOperator(Operator::Mul), vec![7..8])])
+        );
+    }
+
+    #[test]
+    fn pre_parse_unknow_fonction() {
+        let calculation = "foo(2) + pi".tokenize().unwrap();
+        assert_eq!(
+            calculation.pre_parse(&Context::default()),
+            Err(vec![Error::new(ErrorKinds::UnknowFonction("foo".to_owned()), vec![0..3])])
+        );
+    }
+
+    #[test]
+    fn parse_precedence() {
+        let expr = parse_str("1 + 2 * 3");
+        assert_eq!(
+            expr,
+            Ok(node(
+                Expr::Int(1, 0..1),
+                Operator::Add,
+                node(Expr::Int(2, 4..5), Operator::Mul, Expr::Int(3, 8..9), 4..9),
+                0..9
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_left_associativity() {
+        let expr = parse_str("8 - 4 - 2");
+        assert_eq!(
+            expr,
+            Ok(node(
+                node(Expr::Int(8, 0..1), Operator::Sub, Expr::Int(4, 4..5), 0..5),
+                Operator::Sub,
+                Expr::Int(2, 8..9),
+                0..9
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_pow_right_associativity() {
+        let expr = parse_str("2^3^2");
+        assert_eq!(
+            expr,
+            Ok(node(
+                Expr::Int(2, 0..1),
+                Operator::Pow,
+                node(Expr::Int(3, 2..3), Operator::Pow, Expr::Int(2, 4..5), 2..5),
+                0..5
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_sign_and_sub() {
+        let expr = parse_str("-2-3");
+        assert_eq!(
+            expr,
+            Ok(node(
+                Expr::Unary(UnaryOp::Neg, Box::new(Expr::Int(2, 1..2)), 0..2),
+                Operator::Sub,
+                Expr::Int(3, 3..4),
+                0..4
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_sign_precedence() {
+        let expr = parse_str("-2^2 * 3");
+        assert_eq!(
+            expr,
+            Ok(node(
+                Expr::Unary(
+                    UnaryOp::Neg,
+                    Box::new(node(Expr::Int(2, 1..2), Operator::Pow, Expr::Int(2, 3..4), 1..4)),
+                    0..4
+                ),
+                Operator::Mul,
+                Expr::Int(3, 7..8),
+                0..8
+            ))
+        );
+        let expr = parse_str("2^-+x");
+        assert_eq!(
+            expr,
+            Ok(node(
+                Expr::Int(2, 0..1),
+                Operator::Pow,
+                Expr::Unary(
+                    UnaryOp::Neg,
+                    Box::new(Expr::Unary(UnaryOp::Plus, Box::new(Expr::Variable("x".to_owned(), 4..5)), 3..5)),
+                    2..5
+                ),
+                0..5
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_implicit_multiplication() {
+        let expr = parse_str("1/2(3)");
+        assert_eq!(
+            expr,
+            Ok(node(
+                Expr::Int(1, 0..1),
+                Operator::Div,
+                node(Expr::Int(2, 2..3), Operator::Mul, Expr::Int(3, 4..5), 2..6),
+                0..6
+            ))
+        );
+        let expr = parse_str("2x^2");
+        assert_eq!(
+            expr,
+            Ok(node(
+                Expr::Int(2, 0..1),
+                Operator::Mul,
+                node(Expr::Variable("x".to_owned(), 1..2), Operator::Pow, Expr::Int(2, 3..4), 1..4),
+                0..4
+            ))
+        );
+        let expr = parse_str("(a)(b)");
+        assert_eq!(
+            expr,
+            Ok(node(
+                Expr::Variable("a".to_owned(), 1..2),
+                Operator::Mul,
+                Expr::Variable("b".to_owned(), 4..5),
+                0..6
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_strict_mode() {
+        let mut ctx = Context::new();
+        ctx.set_implicit_multiplication(false);
+        let calculation = "3(x+1)".tokenize().unwrap();
+        assert_eq!(
+            calculation.pre_parse(&ctx).unwrap().parse(&ctx),
+            Err(vec![Error::new(ErrorKinds::MissingOperator, vec![1..2])])
+        );
+    }
+
+    #[test]
+    fn parse_function_and_const() {
+        let expr = parse_str("sin(pi // 2)");
+        assert_eq!(
+            expr,
+            Ok(Expr::Function(Box::new(Function::Sin(
+                node(Expr::Const(Const::Pi(4..6)), Operator::FDiv, Expr::Int(2, 10..11), 4..11),
+                0..12
+            ))))
+        );
+    }
+
+    #[test]
+    fn parse_custom_constant() {
+        let mut ctx = Context::new();
+        ctx.set_constant("g", 9.81);
+        ctx.set_constant("e", 2.);
+        let calculation = "g * e - tau".tokenize().unwrap();
+        assert_eq!(
+            calculation.pre_parse(&ctx).unwrap().parse(&ctx),
+            Ok(node(
+                node(
+                    Expr::Const(Const::Custom("g".to_owned(), 9.81, 0..1)),
+                    Operator::Mul,
+                    Expr::Const(Const::Custom("e".to_owned(), 2., 4..5)),
+                    0..5
+                ),
+                Operator::Sub,
+                Expr::Const(Const::Tau(8..11)),
+                0..11
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_root() {
+        let expr = parse_str("√4^2");
+        assert_eq!(
+            expr,
+            Ok(node(
+                Expr::Function(Box::new(Function::Sqrt(Expr::Int(4, 3..4), 0..4))),
+                Operator::Pow,
+                Expr::Int(2, 5..6),
+                0..6
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_variable() {
+        let expr = parse_str("2 ^ x");
+        assert_eq!(
+            expr,
+            Ok(node(Expr::Int(2, 0..1), Operator::Pow, Expr::Variable("x".to_owned(), 4..5), 0..5))
+        );
+    }
+
+    #[test]
+    fn parse_brackets() {
+        let expr = parse_str("(1 + 2) * 3");
+        assert_eq!(
+            expr,
+            Ok(node(
+                node(Expr::Int(1, 1..2), Operator::Add, Expr::Int(2, 5..6), 1..6),
+                Operator::Mul,
+                Expr::Int(3, 10..11),
+                0..11
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_interval() {
+        let expr = parse_str("2*[1, 3]±1");
+        assert_eq!(
+            expr,
+            Ok(node(
+                Expr::Int(2, 0..1),
+                Operator::Mul,
+                node(
+                    Expr::Interval(Box::new(Expr::Int(1, 3..4)), Box::new(Expr::Int(3, 6..7)), 2..8),
+                    Operator::PlusMinus,
+                    Expr::Int(1, 10..11),
+                    2..11
+                ),
+                0..11
+            ))
+        );
+        let expr = parse_str("2[1, 3]±1");
+        assert_eq!(
+            expr,
+            Ok(node(
+                node(
+                    Expr::Int(2, 0..1),
+                    Operator::Mul,
+                    Expr::Interval(Box::new(Expr::Int(1, 2..3)), Box::new(Expr::Int(3, 5..6)), 1..7),
+                    0..7
+                ),
+                Operator::PlusMinus,
+                Expr::Int(1, 9..10),
+                0..10
+            ))
+        );
+        let expr = parse_str("[1, 2)");
+        assert_eq!(expr, Err(vec![Error::new(ErrorKinds::UnexeptedBrack, vec![5..6])]));
+        let expr = parse_str("[1]");
+        assert_eq!(expr, Err(vec![Error::new(ErrorKinds::MissingOperator, vec![2..3])]));
+    }
+
+    #[test]
+    fn parse_missing_operand() {
+        let expr = parse_str("2 +");
+        assert_eq!(expr, Err(vec![Error::new(ErrorKinds::MissingOperand, vec![3..3])]));
+        let expr = parse_str("max(1, )");
+        assert_eq!(expr, Err(vec![Error::new(ErrorKinds::MissingOperand, vec![7..8])]));
+    }
+
+    #[test]
+    fn parse_missing_operator() {
+        let expr = parse_str("2 3");
+        assert_eq!(expr, Err(vec![Error::new(ErrorKinds::MissingOperator, vec![2..3])]));
+    }
+
+    #[test]
+    fn parse_arguments() {
+        let expr = parse_str("logb(8, -2)");
+        assert_eq!(
+            expr,
+            Ok(Expr::Function(Box::new(Function::LogBased(
+                Expr::Int(8, 5..6),
+                Expr::Unary(UnaryOp::Neg, Box::new(Expr::Int(2, 9..10)), 8..10),
+                0..11
+            ))))
+        );
+        let expr = parse_str("max()");
+        assert_eq!(
+            expr,
+            Err(vec![Error::new(ErrorKinds::WrongArgumentCount { expected: 1, found: 0 }, vec![0..5])])
+        );
+    }
+
+    #[test]
+    fn parse_wrong_argument_count() {
+        let expr = parse_str("1 + sqrt(4, 9)");
+        assert_eq!(
+            expr,
+            Err(vec![Error::new(ErrorKinds::WrongArgumentCount { expected: 1, found: 2 }, vec![4..14])])
+        );
+        let expr = parse_str("round(1, 2, 3)");
+        assert_eq!(
+            expr,
+            Err(vec![Error::new(ErrorKinds::WrongArgumentCount { expected: 2, found: 3 }, vec![0..14])])
+        );
+    }
+
+    #[test]
+    fn parse_unexepted_separator() {
+        let expr = parse_str("(1, 2)");
+        assert_eq!(expr, Err(vec![Error::new(ErrorKinds::UnexeptedSeparator, vec![2..3])]));
+    }
+
+    #[test]
+    fn parse_custom_function() {
+        let mut ctx = Context::new();
+        ctx.set_function("clamp", 1, |args| args[0].clamp(0., 1.));
+        let expr = "clamp(x)".tokenize().unwrap().pre_parse(&ctx).unwrap().parse(&ctx);
+        assert_eq!(
+            expr,
+            Ok(Expr::Function(Box::new(Function::Custom(
+                ctx.get_function("clamp").unwrap().clone(),
+                vec![Expr::Variable("x".to_owned(), 6..7)],
+                0..8
+            ))))
+        );
+    }
+
+    #[test]
+    fn parse_custom_function_wrong_arity() {
+        let mut ctx = Context::new();
+        ctx.set_function("lerp", 3, |args| args[0] + (args[1] - args[0]) * args[2]);
+        let expr = "lerp(2, 3)".tokenize().unwrap().pre_parse(&ctx).unwrap().parse(&ctx);
+        assert_eq!(
+            expr,
+            Err(vec![Error::new(ErrorKinds::WrongArgumentCount { expected: 3, found: 2 }, vec![0..10])])
+        );
+    }
 }
@@ -0,0 +1,342 @@
+use std::cmp::Ordering;
+
+use crate::ast::consts::Const;
+use crate::ast::expr::Expr;
+use crate::ast::function::Function;
+use crate::ast::token::operator::{Operator, UnaryOp};
+use crate::ast::tree::TreeNode;
+use crate::context::Context;
+use crate::eval::Eval;
+use crate::maph_error::Span;
+use crate::value::Value;
+
+// Simplify `expr` from the leaves up, see `Expr::simplify`.
+pub(crate) fn simplify(expr: Expr, ctx: &Context) -> Expr {
+    match expr {
+        Expr::Node(node) => simplify_node(*node, ctx),
+        Expr::Function(fnc) => simplify_function(*fnc, ctx),
+        Expr::Unary(op, expr, span) => match (op, simplify(*expr, ctx)) {
+            (UnaryOp::Neg, Expr::Unary(UnaryOp::Neg, expr, _)) => *expr,
+            (op, expr) => fold(Expr::Unary(op, Box::new(expr), span), ctx),
+        },
+        Expr::Interval(lo, hi, span) => {
+            Expr::Interval(Box::new(simplify(*lo, ctx)), Box::new(simplify(*hi, ctx)), span)
+        }
+        leaf => leaf,
+    }
+}
+
+fn simplify_node(node: TreeNode, ctx: &Context) -> Expr {
+    let TreeNode { l_expr, op, r_expr, span } = node;
+    let (l, r) = (simplify(l_expr, ctx), simplify(r_expr, ctx));
+    match op {
+        Operator::Add | Operator::Mul => simplify_chain(op, l, r, span, ctx),
+        Operator::Sub if is_zero(&r) => l,
+        Operator::Sub if is_zero(&l) => fold(Expr::Unary(UnaryOp::Neg, Box::new(r), span), ctx),
+        // `x - x` is only known to be `0` for a finite number, and its type.
+        Operator::Sub if same(&l, &r) && is_finite_constant(&l, ctx) => evaluated(node_expr(l, op, r, span), ctx),
+        // `x / 1` is a float when `x` is an integer, but `x ^ 1` keeps its type.
+        Operator::Pow if is_one(&r) => l,
+        // Commutative operators with operands in the canonical order.
+        Operator::BitAnd | Operator::BitOr | Operator::Xor | Operator::Eq | Operator::Ne
+            if order(&r, &l) == Ordering::Less =>
+        {
+            fold(node_expr(r, op, l, span), ctx)
+        }
+        op => fold(node_expr(l, op, r, span), ctx),
+    }
+}
+
+// A chain of `+` or `*`, as `x * 2 * 3`, with its numbers folded together and
+// its operands in the canonical order: `6 * x`, but `x + 6`.
+fn simplify_chain(op: Operator, l: Expr, r: Expr, span: Span, ctx: &Context) -> Expr {
+    let mut operands = Vec::new();
+    flatten(l, op, &mut operands);
+    flatten(r, op, &mut operands);
+    let (numbers, mut others): (Vec<_>, Vec<_>) = operands.into_iter().partition(is_literal);
+    others.sort_by(order);
+
+    let number = numbers.into_iter().reduce(|acc, n| {
+        let span = join(acc.span(), n.span());
+        fold(node_expr(acc, op, n, span), ctx)
+    });
+    // `x * 0` is `0` for finite numbers only, as a variable could be unknown,
+    // infinite or hold an error to report.
+    let zero = op == Operator::Mul
+        && matches!(number, Some(Expr::Int(0, _)))
+        && others.iter().all(|expr| is_finite_constant(expr, ctx));
+    let number = match number {
+        Some(n) if op == Operator::Add && is_zero(&n) => None,
+        Some(n) if op == Operator::Mul && is_one(&n) => None,
+        number => number,
+    };
+    let operands = match (op, number) {
+        (_, None) => others,
+        (Operator::Add, Some(n)) => others.into_iter().chain(Some(n)).collect(),
+        (_, Some(n)) => std::iter::once(n).chain(others).collect(),
+    };
+    let chain = operands
+        .into_iter()
+        .reduce(|acc, expr| {
+            let span = join(acc.span(), expr.span());
+            node_expr(acc, op, expr, span)
+        })
+        .unwrap_or_else(|| Expr::Int(if op == Operator::Add { 0 } else { 1 }, span));
+    if zero { evaluated(chain, ctx) } else { chain }
+}
+
+fn simplify_function(fnc: Function, ctx: &Context) -> Expr {
+    // Only the chosen branch of an `if` with a constant condition is kept.
+    if let Function::If(condition, then, otherwise, span) = fnc {
+        let condition = simplify(condition, ctx);
+        if is_exact(&condition) {
+            if let Ok(value) = condition.eval_with(ctx) {
+                return simplify(if value.is_truthy() { then } else { otherwise }, ctx);
+            }
+        }
+        let fnc = Function::If(condition, simplify(then, ctx), simplify(otherwise, ctx), span);
+        return Expr::Function(Box::new(fnc));
+    }
+    fold(Expr::Function(Box::new(fnc.map_args(|arg| simplify(arg, ctx)))), ctx)
+}
+
+// Replace an operation on integers by its result when it is an integer too,
+// which is exact with every backend of `Expr::eval_numeric`, or the negation
+// of a number. Other results, such as `0.1 + 0.2` or `7 / 2`, would be rounded
+// to a float.
+fn fold(expr: Expr, ctx: &Context) -> Expr {
+    let operands_are_integers = match &expr {
+        Expr::Node(node) => is_integer(&node.l_expr) && is_integer(&node.r_expr),
+        // A literal with more digits than its float is kept as written.
+        Expr::Unary(UnaryOp::Neg, expr, _) => matches!(**expr, Expr::Int(..) | Expr::Number(_, None, _)),
+        Expr::Unary(_, expr, _) => is_integer(expr),
+        Expr::Function(fnc) => fnc.args().into_iter().all(is_integer),
+        _ => false,
+    };
+    if !operands_are_integers {
+        return expr;
+    }
+    match expr.eval_with(ctx) {
+        Ok(Value::Int(n)) => Expr::Int(n, expr.span().clone()),
+        Ok(Value::Float(n)) if matches!(expr, Expr::Unary(..)) => Expr::Number(n, None, expr.span().clone()),
+        _ => expr,
+    }
+}
+
+// Replace `expr`, an operation on finite numbers giving `0`, by its result,
+// which is exact, of the type `Expr::eval_with` gives, as `0.` for `pi * 0`.
+fn evaluated(expr: Expr, ctx: &Context) -> Expr {
+    match expr.eval_with(ctx) {
+        Ok(Value::Int(n)) => Expr::Int(n, expr.span().clone()),
+        Ok(Value::Float(n)) => Expr::Number(n, None, expr.span().clone()),
+        _ => expr,
+    }
+}
+
+// Push the operands of the chain of `op` in `expr` to `operands`.
+fn flatten(expr: Expr, op: Operator, operands: &mut Vec<Expr>) {
+    match expr {
+        Expr::Node(node) if node.op == op => {
+            let TreeNode { l_expr, r_expr, .. } = *node;
+            flatten(l_expr, op, operands);
+            flatten(r_expr, op, operands);
+        }
+        expr => operands.push(expr),
+    }
+}
+
+// Canonical order of operands: numbers, constants, variables by name, then the
+// other expressions as written.
+fn order(l: &Expr, r: &Expr) -> Ordering {
+    fn rank(expr: &Expr) -> (u8, &str) {
+        match expr {
+            Expr::Number(..) | Expr::Int(..) => (0, ""),
+            Expr::Const(_) => (1, ""),
+            Expr::Variable(name, _) => (2, name),
+            _ => (3, ""),
+        }
+    }
+    rank(l).cmp(&rank(r))
+}
+
+fn is_literal(expr: &Expr) -> bool {
+    matches!(expr, Expr::Number(..) | Expr::Int(..))
+}
+
+fn is_integer(expr: &Expr) -> bool {
+    matches!(expr, Expr::Int(..))
+}
+
+// Identities only drop integers, `x + 0.` being a float even when `x` is not.
+fn is_zero(expr: &Expr) -> bool {
+    matches!(expr, Expr::Int(0, _))
+}
+
+fn is_one(expr: &Expr) -> bool {
+    matches!(expr, Expr::Int(1, _))
+}
+
+// Whether `expr` is a number or a constant with a finite value, which can not
+// fail.
+fn is_finite_constant(expr: &Expr, ctx: &Context) -> bool {
+    match expr {
+        Expr::Int(..) => true,
+        Expr::Number(n, ..) => n.is_finite(),
+        Expr::Const(c) => matches!(c.eval_with(ctx), Ok(Value::Float(n)) if n.is_finite()),
+        _ => false,
+    }
+}
+
+// Whether `expr` is a condition on integers, which has the same value with
+// every backend. Operations on integers with an integer result being folded,
+// only comparisons and logical operators are left.
+fn is_exact(expr: &Expr) -> bool {
+    match expr {
+        Expr::Int(..) => true,
+        Expr::Node(node) if node.op.is_boolean() => is_exact(&node.l_expr) && is_exact(&node.r_expr),
+        Expr::Unary(UnaryOp::Not, expr, _) => is_exact(expr),
+        _ => false,
+    }
+}
+
+// Whether `l` and `r` are the same expression, regardless of their spans.
+pub(crate) fn same(l: &Expr, r: &Expr) -> bool {
+    match (l, r) {
+        (Expr::Node(l), Expr::Node(r)) => l.op == r.op && same(&l.l_expr, &r.l_expr) && same(&l.r_expr, &r.r_expr),
+        (Expr::Number(l, l_digits, _), Expr::Number(r, r_digits, _)) => l == r && l_digits == r_digits,
+        (Expr::Int(l, _), Expr::Int(r, _)) => l == r,
+        (Expr::Const(Const::Custom(l, ..)), Expr::Const(Const::Custom(r, ..))) => l == r,
+        (Expr::Const(l), Expr::Const(r)) => std::mem::discriminant(l) == std::mem::discriminant(r),
+        (Expr::Variable(l, _), Expr::Variable(r, _)) => l == r,
+        (Expr::Unary(l_op, l, _), Expr::Unary(r_op, r, _)) => l_op == r_op && same(l, r),
+        (Expr::Function(l), Expr::Function(r)) => {
+            let (l_args, r_args) = (l.args(), r.args());
+            l.name() == r.name() && l_args.len() == r_args.len() && l_args.iter().zip(r_args).all(|(l, r)| same(l, r))
+        }
+        (Expr::Interval(l_lo, l_hi, _), Expr::Interval(r_lo, r_hi, _)) => same(l_lo, r_lo) && same(l_hi, r_hi),
+        _ => false,
+    }
+}
+
+fn node_expr(l: Expr, op: Operator, r: Expr, span: Span) -> Expr {
+    Expr::Node(Box::new(TreeNode::new(l, op, r, span)))
+}
+
+// Smallest span covering `l` and `r`.
+fn join(l: &Span, r: &Span) -> Span {
+    l.start.min(r.start)..l.end.max(r.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maph_error::{Error, ErrorKinds};
+
+    fn simplify_str(input: &str) -> Expr {
+        input.parse::<Expr>().unwrap().simplify()
+    }
+
+    fn variable(name: &str, span: Span) -> Expr {
+        Expr::Variable(name.to_owned(), span)
+    }
+
+    #[test]
+    fn simplify_identities() {
+        assert_eq!(simplify_str("(x*1)+0"), variable("x", 1..2));
+        assert_eq!(simplify_str("x^1 - 0 + 0*3"), variable("x", 0..1));
+        assert_eq!(simplify_str("pi - pi + 1"), node_expr(Expr::Number(0., None, 0..7), Operator::Add, Expr::Int(1, 10..11), 0..11));
+        assert_eq!(simplify_str("0 * inf"), "0 * inf".parse::<Expr>().unwrap());
+        assert_eq!(simplify_str("2 * e * 0"), Expr::Number(0., None, 0..9));
+        assert_eq!(simplify_str("0 - x"), Expr::Unary(UnaryOp::Neg, Box::new(variable("x", 4..5)), 0..5));
+        assert_eq!(simplify_str("--x ^ 1"), variable("x", 2..3));
+    }
+
+    #[test]
+    fn simplify_constant_folding() {
+        assert_eq!(simplify_str("2*3*x"), node_expr(Expr::Int(6, 0..3), Operator::Mul, variable("x", 4..5), 0..5));
+        assert_eq!(simplify_str("x*2*3"), node_expr(Expr::Int(6, 2..5), Operator::Mul, variable("x", 0..1), 0..5));
+        assert_eq!(simplify_str("2^10 // 3 + x"), node_expr(variable("x", 12..13), Operator::Add, Expr::Int(341, 0..9), 0..13));
+        assert_eq!(simplify_str("sqrt(16) / 8 + 0.1"), "sqrt(16) / 8 + 0.1".parse::<Expr>().unwrap());
+        assert_eq!(simplify_str("if(1 < 2, x, 1 / 0)"), variable("x", 10..11));
+        assert_eq!(simplify_str("2pi"), "2pi".parse::<Expr>().unwrap());
+    }
+
+    #[test]
+    fn simplify_canonical_order() {
+        let expr = simplify_str("y + 1 + x + 2");
+        let sum = node_expr(variable("x", 8..9), Operator::Add, variable("y", 0..1), 0..9);
+        assert_eq!(expr, node_expr(sum, Operator::Add, Expr::Int(3, 4..13), 0..13));
+        assert_eq!(simplify_str("y == x"), node_expr(variable("x", 5..6), Operator::Eq, variable("y", 0..1), 0..6));
+        assert!(same(&simplify_str("x * y * 2"), &simplify_str("2y * x")));
+    }
+
+    #[test]
+    fn simplify_keeps_type() {
+        let mut ctx = Context::new();
+        ctx.set_variable("x", 7);
+        for input in ["7/1", "x / 1", "x + 0.", "x * 1.", "2 * 3.5", "if(0.1 + 0.2 == 0.3, 1, 0.)"] {
+            let expr = input.parse::<Expr>().unwrap();
+            assert_eq!(expr.clone().simplify().eval_with(&ctx), expr.eval_with(&ctx), "{}", input);
+        }
+        assert_eq!(simplify_str("7/1").eval(), Ok(Value::Float(7.)));
+    }
+
+    #[cfg(any(feature = "rational", feature = "decimal"))]
+    #[test]
+    fn simplify_exact_backends() {
+        let mut ctx = Context::new();
+        ctx.set_variable("x", 3);
+        let inputs = [
+            "0.1 + 0.2 - 0.3",
+            "x * 0.1 * 3 + 7 / 2",
+            "if(0.1 + 0.2 == 0.3, 1, 0)",
+            "(2/3)^2 + x % 2.5 + 10 // 4",
+            "1 / 3 + 0.25 * 4 - -0.5",
+        ];
+        for input in inputs {
+            let expr = input.parse::<Expr>().unwrap();
+            let simplified = expr.clone().simplify();
+            #[cfg(feature = "rational")]
+            assert_eq!(
+                simplified.eval_numeric::<crate::Ratio>(&ctx),
+                expr.eval_numeric::<crate::Ratio>(&ctx),
+                "{}",
+                input
+            );
+            #[cfg(feature = "decimal")]
+            assert_eq!(
+                simplified.eval_numeric::<crate::BigDecimal>(&ctx),
+                expr.eval_numeric::<crate::BigDecimal>(&ctx),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn simplify_keeps_errors() {
+        let mut ctx = Context::new();
+        ctx.set_variable("x", 2.);
+        let expr = simplify_str("x + 1 / (2 - 2) * 0");
+        assert_eq!(expr.eval_with(&ctx), Err(Error::new(ErrorKinds::DivisionByZero, vec![9..14])));
+        let expr = simplify_str("sqrt(-4) - sqrt(-4)");
+        assert_eq!(expr.eval(), Err(Error::new(ErrorKinds::NegativeSqrt, vec![0..8])));
+
+        let input = "3x^2 * 1 - 2 * (x + 0) / 4 + ln(e) * max(x, 1)";
+        let expr = input.parse::<Expr>().unwrap();
+        assert_eq!(expr.clone().simplify().eval_with(&ctx), expr.eval_with(&ctx));
+    }
+
+    #[test]
+    fn simplify_same_as_eval() {
+        let inputs = ["sqrt(x) * 0", "1/x - 1/x", "0 * y", "x * 0", "x - x", "sin(x) - sin(x) + 1", "x * 1 + 0"];
+        for x in [-4., 0., 2.5] {
+            let mut ctx = Context::new();
+            ctx.set_variable("x", x);
+            for input in inputs {
+                let expr = input.parse::<Expr>().unwrap();
+                assert_eq!(expr.clone().simplify().eval_with(&ctx), expr.eval_with(&ctx), "{} with x = {}", input, x);
+            }
+        }
+    }
+}
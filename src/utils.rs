@@ -2,7 +2,8 @@
 // It dedup what kind of element you want (e.g. all 0 in a vec of u32) for n time add replace these duplicate element by what you want.
 // ex : same = 0, to = 9, occurence = 2
 //      [0, 0, 0, 1] => [9, 0, 1]
-pub trait DedupReplaceFor{
+#[allow(dead_code)]
+pub(crate) trait DedupReplaceFor{
     type Item;
     
     fn dedup_and_replace_for(&mut self, same: &Self::Item, to: Self::Item, occurence: usize);
@@ -46,7 +47,7 @@ where
 
     fn dedup_and_replace_for_by_then_build(&mut self, occurence: usize, same_as: impl Fn(&Self::Item) -> bool, build: impl Fn(Self::Item) -> Self::Item) {
         if occurence == 0 {
-            for item in self.into_iter() {
+            for item in self.iter_mut() {
                 *item = build(*item)
             }
             return;
@@ -0,0 +1,132 @@
+use std::fmt;
+
+use crate::maph_error::{Error, ErrorKinds, Span};
+
+/// Result of the evaluation of an expression.
+///
+/// Integers stay integers through `+`, `-`, `*`, `%`, `//` and `^` with a
+/// non-negative exponent, and become floats when mixed with a float, divided
+/// with `/` or on overflow. Booleans come from comparisons and logical
+/// operators and never mix with numbers, except as conditions where a non-zero
+/// number is true.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+/// Type of a [`Value`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Int,
+    Float,
+    Bool,
+}
+
+impl Value {
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Self::Int(_) => ValueType::Int,
+            Self::Float(_) => ValueType::Float,
+            Self::Bool(_) => ValueType::Bool,
+        }
+    }
+
+    /// The value as a float, `None` for a boolean.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(n) => Some(*n as f64),
+            Self::Float(n) => Some(*n),
+            Self::Bool(_) => None,
+        }
+    }
+
+    /// Whether the value holds as a condition: `true` or a non-zero number.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Self::Int(n) => *n != 0,
+            Self::Float(n) => *n != 0.,
+            Self::Bool(b) => *b,
+        }
+    }
+
+    /// The value as a float, a boolean being an error at `span`.
+    pub(crate) fn to_number(self, span: &Span) -> Result<f64, Error> {
+        self.as_f64()
+            .ok_or_else(|| Error::new(ErrorKinds::UnexpectedBoolean, vec![span.clone()]))
+    }
+
+    /// The value as an integer, a float with a fractional part being an error at `span`.
+    pub(crate) fn to_integer(self, span: &Span) -> Result<i64, Error> {
+        match self {
+            Self::Int(n) => Ok(n),
+            Self::Float(n) if n.fract() != 0. || n < i64::MIN as f64 || n >= i64::MAX as f64 => {
+                Err(Error::new(ErrorKinds::NotAnInteger(n), vec![span.clone()]))
+            }
+            Self::Float(n) => Ok(n as i64),
+            Self::Bool(_) => Err(Error::new(ErrorKinds::UnexpectedBoolean, vec![span.clone()])),
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Self::Int(n)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Self::Float(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int(n) => write!(f, "{}", n),
+            Self::Float(n) => write!(f, "{}", n),
+            Self::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int => f.write_str("integer"),
+            Self::Float => f.write_str("float"),
+            Self::Bool => f.write_str("boolean"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coerce_value() {
+        assert_eq!(Value::Int(2).to_number(&(0..1)), Ok(2.));
+        assert_eq!(Value::Float(4.).to_integer(&(0..1)), Ok(4));
+        assert_eq!(Value::Float(4.5).to_integer(&(0..3)), Err(Error::new(ErrorKinds::NotAnInteger(4.5), vec![0..3])));
+        assert_eq!(Value::Bool(true).to_number(&(2..6)), Err(Error::new(ErrorKinds::UnexpectedBoolean, vec![2..6])));
+        assert!(Value::Float(0.5).is_truthy());
+        assert!(!Value::Int(0).is_truthy());
+    }
+
+    #[test]
+    fn display_value() {
+        assert_eq!(Value::Int(-3).to_string(), "-3");
+        assert_eq!(Value::Float(2.5).to_string(), "2.5");
+        assert_eq!(Value::Bool(false).to_string(), "false");
+        assert_eq!(ValueType::Bool.to_string(), "boolean");
+    }
+}